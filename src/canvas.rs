@@ -1,6 +1,9 @@
 pub mod background;
+pub mod grading;
 pub mod graphics;
 pub mod image;
+pub mod indexed;
+pub mod minimap;
 pub mod point;
 pub mod rectangle;
 pub mod sprite;
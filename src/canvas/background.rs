@@ -18,3 +18,16 @@ pub fn render(buffer: &mut [u8], color: &Color) {
         }
     }
 }
+
+// Blend the whole buffer towards a color, an amount of 1 covers it
+pub fn fade(buffer: &mut [u8], color: &Color, amount: f32) {
+    let amount = amount.clamp(0.0, 1.0);
+    let target = [color.red, color.green, color.blue];
+
+    for pixel in buffer.chunks_exact_mut(4) {
+        for (channel, &target) in pixel.iter_mut().zip(target.iter()) {
+            let value = *channel as f32 + (target as f32 - *channel as f32) * amount;
+            *channel = value.round() as u8;
+        }
+    }
+}
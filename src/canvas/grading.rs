@@ -0,0 +1,30 @@
+use crate::graphics::lut::{Grading, Lut};
+
+pub fn render(buffer: &mut [u8], grading: &Grading) {
+    if !grading.active() {
+        return;
+    }
+
+    let fading = grading.fading();
+    let progress = grading.progress();
+
+    let sample = |lut: Option<&Lut>, pixel: &[u8]| match lut {
+        Some(lut) => lut.sample(pixel[0], pixel[1], pixel[2]),
+        None => [pixel[0], pixel[1], pixel[2]],
+    };
+
+    for pixel in buffer.chunks_exact_mut(4) {
+        let a = sample(grading.current(), pixel);
+
+        if !fading {
+            pixel[..3].copy_from_slice(&a);
+            continue;
+        }
+
+        let b = sample(grading.target(), pixel);
+
+        for c in 0..3 {
+            pixel[c] = (a[c] as f32 * (1.0 - progress) + b[c] as f32 * progress).round() as u8;
+        }
+    }
+}
@@ -1,12 +1,14 @@
 use crate::graphics::graphic::Graphic;
+use crate::graphics::image::Image;
 
 use super::background;
 use super::image;
+use super::indexed;
 use super::point;
 use super::rectangle;
 use super::sprite;
 
-pub fn render(buffer: &mut [u8], width: u32, height: u32, graphics: &Vec<&Graphic>) {
+pub fn render(buffer: &mut [u8], width: u32, height: u32, graphics: &[&Graphic]) {
     for graphic in graphics.iter() {
         match graphic {
             Graphic::Background { color } => {
@@ -28,7 +30,18 @@ pub fn render(buffer: &mut [u8], width: u32, height: u32, graphics: &Vec<&Graphi
             } => {
                 sprite::render(buffer, width, height, point, rectangle, image);
             }
+            Graphic::Indexed { image } => {
+                indexed::render(buffer, width, height, image);
+            }
             _ => {}
         }
     }
 }
+
+// Draw into an image instead of the frame buffer, in image coordinates
+pub fn render_image(target: &mut Image, graphics: &[&Graphic]) {
+    let width = target.width();
+    let height = target.height();
+
+    render(target.bytes_mut(), width, height, graphics);
+}
@@ -1,6 +1,17 @@
 use crate::graphics::image::Image;
 
 pub fn render(buffer: &mut [u8], width: u32, height: u32, image: &Image) {
+    render_opacity(buffer, width, height, image, 1.0);
+}
+
+// Render with the image alpha scaled by an opacity between 0 and 1
+pub fn render_opacity(buffer: &mut [u8], width: u32, height: u32, image: &Image, opacity: f32) {
+    if opacity <= 0.0 {
+        return;
+    }
+
+    let opacity = (opacity.min(1.0) * 255.0).round() as u32;
+
     let bytes = image.bytes();
 
     let width = width as i32;
@@ -33,11 +44,18 @@ pub fn render(buffer: &mut [u8], width: u32, height: u32, image: &Image) {
         }
 
         if x >= 0 && y >= 0 && x < width && y < height {
-            if bytes[point as usize + 3] > 0 {
-                buffer[index as usize + 0] = bytes[point as usize + 0];
-                buffer[index as usize + 1] = bytes[point as usize + 1];
-                buffer[index as usize + 2] = bytes[point as usize + 2];
-                buffer[index as usize + 3] = bytes[point as usize + 3];
+            let cursor = point as usize;
+            let color = &bytes[cursor..cursor + 4];
+
+            if opacity == 255 {
+                blend(buffer, index as usize, color);
+            } else {
+                let alpha = (color[3] as u32 * opacity / 255) as u8;
+                blend(
+                    buffer,
+                    index as usize,
+                    &[color[0], color[1], color[2], alpha],
+                );
             }
         }
 
@@ -48,3 +66,26 @@ pub fn render(buffer: &mut [u8], width: u32, height: u32, image: &Image) {
         }
     }
 }
+
+// Composite a single RGBA pixel over the buffer
+pub fn blend(buffer: &mut [u8], index: usize, color: &[u8]) {
+    let alpha = color[3] as u32;
+
+    if alpha == 0 {
+        return;
+    }
+
+    if alpha == 255 {
+        buffer[index..index + 4].copy_from_slice(&color[..4]);
+        return;
+    }
+
+    for c in 0..3 {
+        let source = color[c] as u32 * alpha;
+        let destination = buffer[index + c] as u32 * (255 - alpha);
+        buffer[index + c] = ((source + destination) / 255) as u8;
+    }
+
+    let destination = buffer[index + 3] as u32 * (255 - alpha) / 255;
+    buffer[index + 3] = (alpha + destination) as u8;
+}
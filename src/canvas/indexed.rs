@@ -0,0 +1,40 @@
+use crate::graphics::indexed::IndexedImage;
+
+pub fn render(buffer: &mut [u8], width: u32, height: u32, image: &IndexedImage) {
+    let indices = image.indices();
+    let colors = image.colors();
+
+    let width = width as i32;
+    let height = height as i32;
+
+    let x = image.x().round() as i32;
+    let y = image.y().round() as i32;
+
+    if x >= width || y >= height {
+        return;
+    }
+
+    let w = image.width() as i32;
+
+    for (i, value) in indices.iter().enumerate() {
+        let i = i as i32;
+        let x = x + i % w;
+        let y = y + i / w;
+
+        if x < 0 || y < 0 || x >= width || y >= height {
+            continue;
+        }
+
+        let color = match colors.get(*value as usize) {
+            Some(color) if color.alpha > 0 => color,
+            _ => continue,
+        };
+
+        let index = ((x + y * width) * 4) as usize;
+
+        buffer[index] = color.red;
+        buffer[index + 1] = color.green;
+        buffer[index + 2] = color.blue;
+        buffer[index + 3] = color.alpha;
+    }
+}
@@ -0,0 +1,70 @@
+use crate::graphics::color::Color;
+use crate::graphics::minimap::Minimap;
+use crate::graphics::point::Point;
+use crate::graphics::rectangle::Rectangle;
+
+use super::image;
+use super::rectangle;
+
+pub fn render(
+    buffer: &mut [u8],
+    width: u32,
+    height: u32,
+    minimap: &Minimap,
+    subject: &Rectangle,
+    view: &Rectangle,
+) {
+    image::render(buffer, width, height, minimap.image());
+
+    if let Some(fog) = minimap.fog() {
+        image::render(buffer, width, height, fog);
+    }
+
+    let bounds = Rectangle::new(
+        minimap.x(),
+        minimap.y(),
+        minimap.width() as f32,
+        minimap.height() as f32,
+    );
+
+    let view = clip(&minimap.project_rectangle(view), &bounds);
+    outline(buffer, width, height, &view, &minimap.view_color());
+
+    let center = Point::new(
+        subject.x + subject.width / 2.0,
+        subject.y + subject.height / 2.0,
+    );
+    let point = minimap.project(&center);
+    let marker = Rectangle::new(point.x.floor() - 1.0, point.y.floor() - 1.0, 2.0, 2.0);
+
+    rectangle::render(buffer, width, height, &marker, &minimap.subject_color());
+}
+
+fn clip(rectangle: &Rectangle, bounds: &Rectangle) -> Rectangle {
+    let x = rectangle.x.max(bounds.x);
+    let y = rectangle.y.max(bounds.y);
+    let w = (rectangle.x + rectangle.width).min(bounds.x + bounds.width) - x;
+    let h = (rectangle.y + rectangle.height).min(bounds.y + bounds.height) - y;
+
+    Rectangle::new(x, y, w.max(0.0), h.max(0.0))
+}
+
+fn outline(buffer: &mut [u8], width: u32, height: u32, r: &Rectangle, color: &Color) {
+    let (x, y) = (r.x.round(), r.y.round());
+    let (w, h) = (r.width.round(), r.height.round());
+
+    if w < 1.0 || h < 1.0 {
+        return;
+    }
+
+    let edges = [
+        Rectangle::new(x, y, w, 1.0),
+        Rectangle::new(x, y + h - 1.0, w, 1.0),
+        Rectangle::new(x, y, 1.0, h),
+        Rectangle::new(x + w - 1.0, y, 1.0, h),
+    ];
+
+    for edge in edges.iter() {
+        rectangle::render(buffer, width, height, edge, color);
+    }
+}
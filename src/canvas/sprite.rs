@@ -2,6 +2,8 @@ use crate::graphics::image::Image;
 use crate::graphics::point::Point;
 use crate::graphics::rectangle::Rectangle;
 
+use super::image::blend;
+
 pub fn render(
     buffer: &mut [u8],
     width: u32,
@@ -64,12 +66,8 @@ pub fn render(
         }
 
         if x >= 0 && y >= 0 && x < width && y < height {
-            if bytes[cursor as usize + 3] > 0 {
-                buffer[index as usize + 0] = bytes[cursor as usize + 0];
-                buffer[index as usize + 1] = bytes[cursor as usize + 1];
-                buffer[index as usize + 2] = bytes[cursor as usize + 2];
-                buffer[index as usize + 3] = bytes[cursor as usize + 3];
-            }
+            let cursor = cursor as usize;
+            blend(buffer, index as usize, &bytes[cursor..cursor + 4]);
         }
 
         point += 4;
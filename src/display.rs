@@ -1,4 +1,8 @@
 pub mod surface;
+#[cfg(unix)]
+pub mod terminal;
 pub mod window;
 
+#[cfg(unix)]
+pub use terminal::run as run_terminal;
 pub use window::run;
@@ -0,0 +1,328 @@
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io::{self, Read, Write};
+use std::mem;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::window::{Graphics, WindowTime};
+
+const FRAME: Duration = Duration::from_millis(33);
+const DELAY: Duration = Duration::from_millis(550); // Key repeat delay
+const REPEAT: Duration = Duration::from_millis(120); // Key repeat interval
+
+#[derive(Debug, Clone)]
+pub struct TerminalConfiguration {
+    pub width: u32,
+    pub height: u32,
+    pub title: String,
+}
+
+impl TerminalConfiguration {
+    pub fn new(width: u32, height: u32, title: String) -> Self {
+        Self {
+            width,
+            height,
+            title,
+        }
+    }
+}
+
+// Raw mode terminal, restored when dropped
+struct RawTerminal {
+    original: libc::termios,
+}
+
+impl RawTerminal {
+    fn new() -> io::Result<Self> {
+        unsafe {
+            let mut original: libc::termios = mem::zeroed();
+
+            if libc::tcgetattr(libc::STDIN_FILENO, &mut original) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+
+            let mut raw = original;
+            libc::cfmakeraw(&mut raw);
+            raw.c_cc[libc::VMIN] = 0;
+            raw.c_cc[libc::VTIME] = 0;
+
+            if libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &raw) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+
+            Ok(Self { original })
+        }
+    }
+    fn size(&self) -> (u32, u32) {
+        unsafe {
+            let mut size: libc::winsize = mem::zeroed();
+
+            if libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) != 0
+                || size.ws_col == 0
+            {
+                return (80, 24);
+            }
+
+            (size.ws_col as u32, size.ws_row as u32)
+        }
+    }
+}
+
+impl Drop for RawTerminal {
+    fn drop(&mut self) {
+        unsafe {
+            libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &self.original);
+        }
+        let mut stdout = io::stdout();
+        let _ = stdout.write_all(b"\x1b[0m\x1b[?25h\x1b[?1049l");
+        let _ = stdout.flush();
+    }
+}
+
+// Cell of two vertically stacked pixels drawn as an upper half block
+type Cell = ([u8; 3], [u8; 3]);
+
+struct TerminalState {
+    config: TerminalConfiguration,
+    graphics: Box<dyn Graphics>,
+    time: WindowTime,
+    buffer: Vec<u8>,
+    cells: Vec<Option<Cell>>,
+    size: (u32, u32),
+    keys: BTreeMap<String, (Instant, bool)>,
+    output: String,
+    status: String,
+    shown: bool,
+}
+
+impl TerminalState {
+    fn new(config: TerminalConfiguration, graphics: Box<dyn Graphics>) -> Self {
+        let length = (config.width * config.height * 4) as usize;
+
+        Self {
+            config,
+            graphics,
+            time: WindowTime::new(),
+            buffer: vec![0u8; length],
+            cells: Vec::new(),
+            size: (0, 0),
+            keys: BTreeMap::new(),
+            output: String::new(),
+            status: String::new(),
+            shown: true,
+        }
+    }
+    // Forward pressed characters, returns false when the user quits
+    fn input(&mut self, bytes: &[u8]) -> bool {
+        let now = Instant::now();
+        let mut index = 0;
+
+        while index < bytes.len() {
+            let byte = bytes[index];
+            index += 1;
+
+            match byte {
+                0x03 => return false,                         // Ctrl-C
+                0x1b if index == bytes.len() => return false, // Escape
+                0x1b => {
+                    // Skip escape sequences such as arrow keys
+                    if matches!(bytes[index], b'[' | b'O') {
+                        index += 1;
+                        while index < bytes.len() && !(0x40..=0x7e).contains(&bytes[index]) {
+                            index += 1;
+                        }
+                        index += 1;
+                    }
+                }
+                0x20..=0x7e => {
+                    let key = (byte as char).to_string();
+
+                    match self.keys.get_mut(&key) {
+                        Some(entry) => *entry = (now, true),
+                        None => {
+                            self.graphics.input(true, &key);
+                            self.keys.insert(key, (now, false));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        true
+    }
+    // Terminals report no key release, so release keys once repeats stop
+    fn release(&mut self) {
+        let now = Instant::now();
+
+        let released = self
+            .keys
+            .iter()
+            .filter(|(_, (instant, repeated))| {
+                now - *instant > if *repeated { REPEAT } else { DELAY }
+            })
+            .map(|(key, _)| key.clone())
+            .collect::<Vec<String>>();
+
+        for key in released {
+            self.keys.remove(&key);
+            self.graphics.input(false, &key);
+        }
+    }
+    fn update(&mut self) {
+        self.time.run();
+
+        self.graphics
+            .update(self.time.elapsed, self.time.delta, self.time.fps);
+
+        self.graphics
+            .render(&mut self.buffer, self.config.width, self.config.height);
+
+        if let Some(message) = self.graphics.status().pop() {
+            self.status = message;
+            self.shown = false;
+        }
+    }
+    // Downscale the frame to the terminal and write only changed cells, the last row is kept
+    // for the latest status message so it never lands on the frame
+    fn present(&mut self, size: (u32, u32)) -> io::Result<()> {
+        let width = self.config.width;
+        let height = self.config.height;
+
+        let (columns, rows) = (size.0.max(1), size.1.saturating_sub(1).max(1));
+        let scale = (width.div_ceil(columns))
+            .max(height.div_ceil(rows * 2))
+            .max(1);
+
+        let cw = width / scale;
+        let ch = height / scale / 2;
+
+        self.output.clear();
+
+        if self.size != size || self.cells.len() != (cw * ch) as usize {
+            self.size = size;
+            self.cells = vec![None; (cw * ch) as usize];
+            self.output.push_str("\x1b[0m\x1b[2J");
+            self.shown = false;
+        }
+
+        let mut cursor = None;
+        let mut colors: Option<Cell> = None;
+
+        for row in 0..ch {
+            for column in 0..cw {
+                let top = self.sample(column * scale, row * scale * 2, scale);
+                let bottom = self.sample(column * scale, (row * 2 + 1) * scale, scale);
+                let cell = (top, bottom);
+
+                let index = (column + row * cw) as usize;
+
+                if self.cells[index] == Some(cell) {
+                    continue;
+                }
+
+                self.cells[index] = Some(cell);
+
+                if cursor != Some((column, row)) {
+                    let _ = write!(self.output, "\x1b[{};{}H", row + 1, column + 1);
+                }
+
+                if colors.map(|c| c.0) != Some(top) {
+                    let _ = write!(self.output, "\x1b[38;2;{};{};{}m", top[0], top[1], top[2]);
+                }
+
+                if colors.map(|c| c.1) != Some(bottom) {
+                    let _ = write!(
+                        self.output,
+                        "\x1b[48;2;{};{};{}m",
+                        bottom[0], bottom[1], bottom[2]
+                    );
+                }
+
+                self.output.push('▀');
+
+                cursor = Some((column + 1, row));
+                colors = Some(cell);
+            }
+        }
+
+        if !self.shown {
+            self.shown = true;
+            let text = self
+                .status
+                .chars()
+                .take(columns as usize)
+                .collect::<String>();
+            let _ = write!(self.output, "\x1b[0m\x1b[{};1H\x1b[2K{}", rows + 1, text);
+        }
+
+        if self.output.is_empty() {
+            return Ok(());
+        }
+
+        let mut stdout = io::stdout().lock();
+        stdout.write_all(self.output.as_bytes())?;
+        stdout.flush()
+    }
+    // Average color of a square block of pixels
+    fn sample(&self, x: u32, y: u32, scale: u32) -> [u8; 3] {
+        let width = self.config.width;
+        let mut sum = [0u32; 3];
+
+        for py in y..y + scale {
+            for px in x..x + scale {
+                let index = ((px + py * width) * 4) as usize;
+                sum[0] += self.buffer[index] as u32;
+                sum[1] += self.buffer[index + 1] as u32;
+                sum[2] += self.buffer[index + 2] as u32;
+            }
+        }
+
+        let count = scale * scale;
+
+        [
+            (sum[0] / count) as u8,
+            (sum[1] / count) as u8,
+            (sum[2] / count) as u8,
+        ]
+    }
+}
+
+pub fn run(width: u32, height: u32, title: String, state: impl Graphics + 'static) {
+    let configuration = TerminalConfiguration::new(width, height, title);
+    let terminal = RawTerminal::new().expect("Terminal should support raw mode");
+
+    let mut stdout = io::stdout();
+    let _ = write!(
+        stdout,
+        "\x1b]0;{}\x07\x1b[?1049h\x1b[?25l",
+        configuration.title
+    );
+    let _ = stdout.flush();
+
+    let mut terminal_state = TerminalState::new(configuration, Box::new(state));
+    let mut stdin = io::stdin();
+    let mut bytes = [0u8; 64];
+
+    loop {
+        let start = Instant::now();
+
+        let count = stdin.read(&mut bytes).unwrap_or(0);
+
+        if !terminal_state.input(&bytes[..count]) {
+            break;
+        }
+
+        terminal_state.release();
+        terminal_state.update();
+
+        if terminal_state.present(terminal.size()).is_err() {
+            break;
+        }
+
+        if let Some(remaining) = FRAME.checked_sub(start.elapsed()) {
+            thread::sleep(remaining);
+        }
+    }
+}
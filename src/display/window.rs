@@ -3,7 +3,7 @@ use std::time::Instant;
 
 use winit::application::ApplicationHandler;
 use winit::dpi::{LogicalSize, Size};
-use winit::event::{ElementState, KeyEvent, WindowEvent};
+use winit::event::{ElementState, KeyEvent, MouseButton, MouseScrollDelta, WindowEvent};
 use winit::event_loop::{ActiveEventLoop, ControlFlow, EventLoop};
 use winit::keyboard::{Key, KeyCode, PhysicalKey};
 use winit::window::{Window, WindowId};
@@ -12,8 +12,16 @@ use super::surface::SurfaceState;
 
 pub trait Graphics {
     fn input(&mut self, active: bool, key: &str);
+    // Mouse input is optional, the view position of the pointer and buttons by name
+    fn pointer(&mut self, _x: f32, _y: f32) {}
+    fn click(&mut self, _active: bool, _button: &str) {}
+    fn wheel(&mut self, _delta: f32) {}
     fn update(&mut self, time: f32, delta: f32, fps: f32);
     fn render(&mut self, buffer: &mut [u8], width: u32, height: u32);
+    // Messages for the player since the last call, presented by the display
+    fn status(&mut self) -> Vec<String> {
+        Vec::new()
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -46,7 +54,7 @@ impl WindowConfiguration {
     }
 }
 
-struct WindowTime {
+pub(super) struct WindowTime {
     pub elapsed: f32,
     pub delta: f32,
     pub fps: f32,
@@ -58,7 +66,7 @@ struct WindowTime {
 }
 
 impl WindowTime {
-    fn new() -> Self {
+    pub(super) fn new() -> Self {
         Self {
             elapsed: 0.0,
             delta: 0.0,
@@ -70,7 +78,7 @@ impl WindowTime {
             samples: 60,
         }
     }
-    fn run(&mut self) {
+    pub(super) fn run(&mut self) {
         self.elapsed = self.instant.elapsed().as_secs_f32();
         self.delta = self.elapsed - self.previous;
         self.previous = self.elapsed;
@@ -205,6 +213,44 @@ impl<'a> ApplicationHandler for WindowState<'a> {
                         }
                     }
                 }
+                WindowEvent::CursorMoved { position, .. } => {
+                    let surface = self.surface.as_ref().expect("Window surface should exist");
+                    let size = surface.window().inner_size();
+
+                    // The buffer is stretched over the whole window
+                    let x = position.x as f32 / size.width.max(1) as f32 * self.config.width as f32;
+                    let y =
+                        position.y as f32 / size.height.max(1) as f32 * self.config.height as f32;
+
+                    self.graphics
+                        .as_mut()
+                        .expect("Window graphics should exist")
+                        .pointer(x, y);
+                }
+                WindowEvent::MouseInput { state, button, .. } => {
+                    let button = match button {
+                        MouseButton::Left => "left",
+                        MouseButton::Right => "right",
+                        MouseButton::Middle => "middle",
+                        _ => return,
+                    };
+
+                    self.graphics
+                        .as_mut()
+                        .expect("Window graphics should exist")
+                        .click(state.is_pressed(), button);
+                }
+                WindowEvent::MouseWheel { delta, .. } => {
+                    let delta = match delta {
+                        MouseScrollDelta::LineDelta(_, y) => y,
+                        MouseScrollDelta::PixelDelta(position) => position.y.signum() as f32,
+                    };
+
+                    self.graphics
+                        .as_mut()
+                        .expect("Window graphics should exist")
+                        .wheel(delta);
+                }
                 WindowEvent::RedrawRequested => {
                     let surface = self.surface.as_mut().expect("Window surface should exist");
 
@@ -225,6 +271,15 @@ impl<'a> ApplicationHandler for WindowState<'a> {
                         .expect("Window graphics should exist")
                         .update(self.time.elapsed, self.time.delta, self.time.fps);
 
+                    for message in self
+                        .graphics
+                        .as_mut()
+                        .expect("Window graphics should exist")
+                        .status()
+                    {
+                        eprintln!("{}", message);
+                    }
+
                     self.graphics
                         .as_mut()
                         .expect("Window graphics should exist")
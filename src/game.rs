@@ -1,3 +1,5 @@
+pub mod actor;
 pub mod data;
+pub mod editor;
 pub mod logic;
 pub mod state;
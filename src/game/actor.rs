@@ -0,0 +1,239 @@
+use std::collections::BTreeMap;
+
+use crate::graphics::entity::Entity;
+use crate::graphics::graphic::Graphic;
+use crate::graphics::point::Point;
+use crate::graphics::rectangle::Rectangle;
+use crate::graphics::tween::{Easing, Tween};
+use crate::graphics::world::{Exit, Target};
+
+use super::data::waterfall;
+use super::state::State;
+
+// Creates what an entity stands for in the current level
+pub type Spawn = fn(&Entity, &mut State) -> Result<(), String>;
+
+#[derive(Debug, Clone)]
+pub enum Behaviour {
+    Coin(u32),        // Collected on touch for its value
+    Enemy(f32),       // Hurts on touch by its damage
+    Trigger(Trigger), // Applies its reaction when entered
+    Platform,         // One-way surface that carries the player
+}
+
+// What a trigger does when the player enters it
+#[derive(Debug, Clone, Default)]
+pub struct Trigger {
+    pub message: Option<String>, // Printed line
+    pub mood: Option<String>,    // Level default lookup table
+    pub once: bool,              // Removed once entered
+    pub inside: bool,            // Player inside
+}
+
+// Entity instance in a level, the path moves its top left corner
+#[derive(Debug, Clone)]
+pub struct Actor {
+    pub kind: String,
+    pub boundary: Rectangle,
+    pub path: Tween<Point>,
+    pub behaviour: Behaviour,
+    pub active: bool,
+}
+
+impl Actor {
+    pub fn new(kind: &str, boundary: Rectangle, behaviour: Behaviour) -> Self {
+        Self {
+            kind: kind.into(),
+            boundary,
+            path: Tween::still(Point::new(boundary.x, boundary.y)),
+            behaviour,
+            active: true,
+        }
+    }
+    // Move back and forth by an offset at a speed in level units per second
+    pub fn patrol(mut self, offset: Point, speed: f32, easing: Easing) -> Self {
+        let distance = (offset.x * offset.x + offset.y * offset.y).sqrt();
+
+        if distance > 0.0 && speed > 0.0 {
+            let from = Point::new(self.boundary.x, self.boundary.y);
+            let to = Point::new(from.x + offset.x, from.y + offset.y);
+
+            self.path = Tween::new(from, to, distance / speed, easing)
+                .yoyo()
+                .forever();
+        }
+
+        self
+    }
+}
+
+// Spawn functions by entity type
+pub fn spawn_registry() -> BTreeMap<String, Spawn> {
+    let mut registry: BTreeMap<String, Spawn> = BTreeMap::new();
+
+    registry.insert("coin".into(), spawn_coin);
+    registry.insert("door".into(), spawn_exit);
+    registry.insert("enemy".into(), spawn_enemy);
+    registry.insert("exit".into(), spawn_exit);
+    registry.insert("platform".into(), spawn_platform);
+    registry.insert("spawn".into(), spawn_point);
+    registry.insert("trigger".into(), spawn_trigger);
+    registry.insert("waterfall".into(), spawn_waterfall);
+
+    registry
+}
+
+// Instantiate the entities of the level, entities of unknown types are skipped
+pub fn spawn_entities(state: &mut State) {
+    for entity in state.env.entities.clone().iter() {
+        let result = match state.spawns.get(&entity.kind) {
+            Some(spawn) => spawn(entity, state),
+            None => Err(format!("entity type {} is not registered", entity.kind)),
+        };
+
+        if let Err(error) = result {
+            eprintln!(
+                "Entity {} at {}, {}: {}",
+                entity.kind, entity.x, entity.y, error
+            );
+        }
+    }
+}
+
+// Level area of an entity from its width and height properties in tiles
+fn area(entity: &Entity, state: &State, width: f32, height: f32) -> Rectangle {
+    let tw = state.env.tile_width;
+    let th = state.env.tile_height;
+
+    Rectangle::new(
+        entity.x * tw,
+        entity.y * th,
+        entity.f32("width").unwrap_or(width) * tw,
+        entity.f32("height").unwrap_or(height) * th,
+    )
+}
+
+// Entry point named by the name property, start by default
+fn spawn_point(entity: &Entity, state: &mut State) -> Result<(), String> {
+    let name = entity.str("name").unwrap_or("start");
+
+    state
+        .env
+        .entries
+        .insert(name.into(), Point::new(entity.x, entity.y));
+
+    Ok(())
+}
+
+fn spawn_coin(entity: &Entity, state: &mut State) -> Result<(), String> {
+    let value = entity.f32("value").unwrap_or(1.0).max(0.0) as u32;
+
+    let cell = area(entity, state, 1.0, 1.0);
+    let boundary = Rectangle::new(
+        cell.x + cell.width / 2.0 - 4.0,
+        cell.y + cell.height / 2.0 - 4.0,
+        8.0,
+        8.0,
+    );
+
+    let actor = Actor::new(&entity.kind, boundary, Behaviour::Coin(value));
+    state.env.actors.push(actor);
+
+    Ok(())
+}
+
+// Enemies walk right by range tiles and back
+fn spawn_enemy(entity: &Entity, state: &mut State) -> Result<(), String> {
+    let damage = entity.f32("damage").unwrap_or(1.0);
+    let range = entity.f32("range").unwrap_or(3.0) * state.env.tile_width;
+    let speed = entity.f32("speed").unwrap_or(32.0);
+
+    let boundary = area(entity, state, 1.0, 1.0);
+
+    let actor = Actor::new(&entity.kind, boundary, Behaviour::Enemy(damage)).patrol(
+        Point::new(range, 0.0),
+        speed,
+        Easing::Linear,
+    );
+    state.env.actors.push(actor);
+
+    Ok(())
+}
+
+fn spawn_trigger(entity: &Entity, state: &mut State) -> Result<(), String> {
+    let trigger = Trigger {
+        message: entity.str("message").map(|m| m.into()),
+        mood: entity.str("mood").map(|m| m.into()),
+        once: entity.bool("once").unwrap_or(false),
+        inside: false,
+    };
+
+    let boundary = area(entity, state, 1.0, 1.0);
+
+    let actor = Actor::new(&entity.kind, boundary, Behaviour::Trigger(trigger));
+    state.env.actors.push(actor);
+
+    Ok(())
+}
+
+// Waterfalls are palette cycled columns of height tiles, drawn behind the level
+fn spawn_waterfall(entity: &Entity, state: &mut State) -> Result<(), String> {
+    let boundary = area(entity, state, 1.0, 1.0);
+    let tiles = entity.f32("height").unwrap_or(1.0).max(1.0) as u32;
+
+    let image = waterfall(boundary.x, boundary.y, tiles);
+    let position = Point::new(boundary.x, boundary.y);
+
+    state.env.scenery.push((position, Graphic::indexed(image)));
+
+    Ok(())
+}
+
+// Platforms move by dx and dy tiles and back
+fn spawn_platform(entity: &Entity, state: &mut State) -> Result<(), String> {
+    let dx = entity.f32("dx").unwrap_or(0.0) * state.env.tile_width;
+    let dy = entity.f32("dy").unwrap_or(0.0) * state.env.tile_height;
+    let speed = entity.f32("speed").unwrap_or(32.0);
+
+    let boundary = area(entity, state, 2.0, 1.0);
+
+    let actor = Actor::new(&entity.kind, boundary, Behaviour::Platform).patrol(
+        Point::new(dx, dy),
+        speed,
+        Easing::QuadInOut,
+    );
+    state.env.actors.push(actor);
+
+    Ok(())
+}
+
+// Exits lead to an entry of a registered level, doors are entered by pressing up
+fn spawn_exit(entity: &Entity, state: &mut State) -> Result<(), String> {
+    let level = entity.str("level").ok_or("level property is missing")?;
+    let entry = entity.str("entry").unwrap_or("start");
+
+    if state.world.level(level).is_none() {
+        return Err(format!("level {} is not registered", level));
+    }
+
+    let door = entity.kind == "door";
+    let height = if door { 2.0 } else { 1.0 };
+
+    let exit = Exit {
+        area: Rectangle::new(
+            entity.x,
+            entity.y,
+            entity.f32("width").unwrap_or(1.0),
+            entity.f32("height").unwrap_or(height),
+        ),
+        target: Target {
+            level: level.into(),
+            entry: entry.into(),
+        },
+        door,
+    };
+
+    state.env.exits.push(exit);
+
+    Ok(())
+}
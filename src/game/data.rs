@@ -1,7 +1,16 @@
+use std::collections::BTreeMap;
 use std::fmt;
 
-use crate::graphics::animation::{Animation, Animations};
+use crate::graphics::animation::{Condition, StateMachine, Transition};
+use crate::graphics::character::Character;
+use crate::graphics::color::Color;
+use crate::graphics::effect::{Effect, Outline, Shadow};
 use crate::graphics::image::Image;
+use crate::graphics::indexed::{Cycle, IndexedImage};
+use crate::graphics::lut::Lut;
+use crate::graphics::point::Point;
+use crate::graphics::tileset::Tileset;
+use crate::graphics::world::World;
 
 // Window properties
 pub const WIDTH: u32 = 384;
@@ -28,6 +37,12 @@ pub const MASS: f32 = 64.0;
 pub const DENSITY: f32 = 0.25;
 pub const IMPULSE: f32 = 36.0;
 pub const JUMP: f32 = 16.0;
+pub const IMPACT: f32 = 6.0;
+pub const HEALTH: f32 = 3.0;
+pub const HURT: f32 = 1.0; // Invulnerability time after taking damage
+pub const CLIMB: f32 = 2.0; // Climbing speed
+pub const BOUNCE: f32 = 1.0; // Minimum rebound speed
+pub const SLOPE: f32 = 50.0; // Steepest walkable slope in degrees
 
 // Tile properties
 pub const TILE_WIDTH: u32 = 16;
@@ -39,31 +54,82 @@ pub const ENV_Y: i32 = 0;
 pub const ENV_WIDTH: u32 = 768;
 pub const ENV_HEIGHT: u32 = 576;
 
-// Subject properties
-pub const SUB_X: i32 = 32;
-pub const SUB_Y: i32 = 32;
-pub const SUB_WIDTH: u32 = 16;
-pub const SUB_HEIGHT: u32 = 32;
+// Actor properties
+pub const COIN_COLOR: [u8; 4] = [232, 184, 48, 255];
+pub const ENEMY_COLOR: [u8; 4] = [168, 40, 32, 255];
+pub const PLATFORM_TILE: u32 = 0x02; // Terrain tile moving platforms are drawn with
+
+// Waterfall properties
+pub const WATERFALL_START: u8 = 1; // First palette index of the flowing water
+pub const WATERFALL_END: u8 = 4; // Last palette index of the flowing water
+pub const WATERFALL_SPEED: f32 = 8.0; // Palette steps per second
 
 // Sprite properties
-pub const SPRITE_X: i32 = -4;
-pub const SPRITE_Y: i32 = 0;
-pub const SPRITE_WIDTH: u32 = 24;
-pub const SPRITE_HEIGHT: u32 = 32;
+pub const MARGIN_X: f32 = 0.1; // Animation velocity margin x
+pub const MARGIN_Y: f32 = 0.1; // Animation velocity margin y
+
+// Minimap properties
+pub const MINIMAP: bool = true;
+pub const MINIMAP_FOG: bool = true;
+pub const MINIMAP_SCALE: u32 = 2;
+pub const MINIMAP_MARGIN: u32 = 4;
+pub const MINIMAP_SLIDE: f32 = 0.35; // Slide in and out duration
+
+// Editor properties
+pub const EDITOR_SPEED: f32 = 240.0; // Camera speed in pixels per second
+pub const EDITOR_HISTORY: usize = 100; // Undo steps kept
+pub const EDITOR_MARGIN: u32 = 4; // Palette distance to the view edges
+
+// Level transition properties
+pub const TRANSITION: f32 = 0.25; // Fade out and fade in duration
+pub const TRANSITION_COLOR: [u8; 4] = [0, 0, 0, 255];
+pub const DOOR_COLOR: [u8; 4] = [40, 24, 16, 255];
+
+// Sprite effect properties
+pub const OUTLINE: bool = true;
+pub const OUTLINE_WIDTH: u32 = 1;
+pub const OUTLINE_COLOR: [u8; 4] = [24, 16, 8, 255];
+pub const SHADOW: bool = true;
+pub const SHADOW_X: i32 = 2;
+pub const SHADOW_Y: i32 = 1;
+pub const SHADOW_COLOR: [u8; 4] = [0, 0, 0, 96];
 
 // Player and level data
 pub const MAP: &str = include_str!("../../assets/level.map");
+pub const CAVE: &str = include_str!("../../assets/cave.map");
+pub const LEVELS: &str = include_str!("../../assets/levels.json");
+pub const LEVELS_PATH: &str = "assets/levels.json";
+pub const ASSETS_PATH: &str = "assets";
 pub const PATTERN: &[u8] = include_bytes!("../../assets/pattern.png");
 pub const BACKGROUND: &[u8] = include_bytes!("../../assets/background.png");
 pub const ENVIRONMENT: &[u8] = include_bytes!("../../assets/environment.png");
 pub const CHARACTER: &[u8] = include_bytes!("../../assets/character.png");
+pub const WATERFALL: &[u8] = include_bytes!("../../assets/waterfall.png");
+pub const CHARACTER_DEFINITION: &str = include_str!("../../assets/character.json");
+pub const CHARACTER_PATH: &str = "assets/character.json";
+pub const TILESET: &str = include_str!("../../assets/tileset.json");
+pub const TILESET_PATH: &str = "assets/tileset.json";
+
+// Color grading data
+pub const GRADING: &str = "";
+pub const GRADING_FADE: f32 = 1.0;
+pub const DUSK: &[u8] = include_bytes!("../../assets/dusk.png");
+pub const SEPIA: &[u8] = include_bytes!("../../assets/sepia.png");
+pub const UNDERWATER: &[u8] = include_bytes!("../../assets/underwater.png");
+
+// Color grading zones: x, y, width, height and lookup table
+pub const ZONES: &[(i32, i32, u32, u32, &str)] = &[
+    (336, 0, 432, 224, "dusk"),
+    (336, 416, 432, 160, "underwater"),
+];
 
 // Character Sprite Animation Type
-#[derive(Debug)]
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Sprite {
     FacingLeft,
     FacingRight,
     FacingUp,
+    #[default]
     FacingDown,
     RunningLeft,
     RunningRight,
@@ -79,6 +145,12 @@ impl Sprite {
     pub fn str(&self) -> String {
         self.to_string()
     }
+    pub fn airborne(&self) -> bool {
+        matches!(
+            self,
+            Sprite::AirLeft | Sprite::AirRight | Sprite::AirUp | Sprite::AirDown
+        )
+    }
 }
 
 impl fmt::Display for Sprite {
@@ -87,70 +159,185 @@ impl fmt::Display for Sprite {
     }
 }
 
-// Create player character animations from spritesheet
-pub fn subject_animations() -> (Image, Animations) {
-    let width = SPRITE_WIDTH;
-    let height = SPRITE_HEIGHT;
-
-    let fb = Image::from_bytes;
-    let st = Image::sprite_to_texture_list;
-
-    let message = "Sprite should contain valid image data";
-
-    let mut animations = Animations::new();
-    let mut spritesheet = fb(SPRITE_X as f32, SPRITE_Y as f32, CHARACTER).expect(message);
-
-    let indices_facing_left = [16];
-    let indices_facing_right = [24];
-    let indices_facing_up = [8];
-    let indices_facing_down = [0];
-    let indices_air_left = [22];
-    let indices_air_right = [30];
-    let indices_air_up = [14];
-    let indices_air_down = [2];
-    let indices_run_left = [16, 17, 18, 19, 20, 21, 22, 23];
-    let indices_run_right = [24, 25, 26, 27, 28, 29, 30, 31];
-    let indices_run_up = [8, 9, 10, 11, 12, 13, 14, 15];
-    let indices_run_down = [0, 1, 3, 4, 5, 6, 7];
-
-    let frames_facing_left = st(&mut spritesheet, width, height, &indices_facing_left);
-    let frames_facing_right = st(&mut spritesheet, width, height, &indices_facing_right);
-    let frames_facing_up = st(&mut spritesheet, width, height, &indices_facing_up);
-    let frames_facing_down = st(&mut spritesheet, width, height, &indices_facing_down);
-    let frames_air_left = st(&mut spritesheet, width, height, &indices_air_left);
-    let frames_air_right = st(&mut spritesheet, width, height, &indices_air_right);
-    let frames_air_up = st(&mut spritesheet, width, height, &indices_air_up);
-    let frames_air_down = st(&mut spritesheet, width, height, &indices_air_down);
-    let frames_run_left = st(&mut spritesheet, width, height, &indices_run_left);
-    let frames_run_right = st(&mut spritesheet, width, height, &indices_run_right);
-    let frames_run_up = st(&mut spritesheet, width, height, &indices_run_up);
-    let frames_run_down = st(&mut spritesheet, width, height, &indices_run_down);
-
-    let animation_facing_left = Animation::new(frames_facing_left);
-    let animation_facing_right = Animation::new(frames_facing_right);
-    let animation_facing_up = Animation::new(frames_facing_up);
-    let animation_facing_down = Animation::new(frames_facing_down);
-    let animation_air_left = Animation::new(frames_air_left);
-    let animation_air_right = Animation::new(frames_air_right);
-    let animation_air_up = Animation::new(frames_air_up);
-    let animation_air_down = Animation::new(frames_air_down);
-    let animation_run_left = Animation::new(frames_run_left);
-    let animation_run_right = Animation::new(frames_run_right);
-    let animation_run_up = Animation::new(frames_run_up);
-    let animation_run_down = Animation::new(frames_run_down);
-
-    animations.add(&Sprite::FacingLeft.str(), animation_facing_left);
-    animations.add(&Sprite::FacingRight.str(), animation_facing_right);
-    animations.add(&Sprite::FacingUp.str(), animation_facing_up);
-    animations.add(&Sprite::FacingDown.str(), animation_facing_down);
-    animations.add(&Sprite::AirLeft.str(), animation_air_left);
-    animations.add(&Sprite::AirRight.str(), animation_air_right);
-    animations.add(&Sprite::AirUp.str(), animation_air_up);
-    animations.add(&Sprite::AirDown.str(), animation_air_down);
-    animations.add(&Sprite::RunningLeft.str(), animation_run_left);
-    animations.add(&Sprite::RunningRight.str(), animation_run_right);
-    animations.add(&Sprite::RunningUp.str(), animation_run_up);
-    animations.add(&Sprite::RunningDown.str(), animation_run_down);
-
-    (spritesheet, animations)
+// Load player character definition from disk, falling back to the embedded one
+pub fn subject_character() -> Character {
+    if std::path::Path::new(CHARACTER_PATH).exists() {
+        match Character::from_uri(CHARACTER_PATH) {
+            Ok(character) => return character,
+            Err(error) => eprintln!("{}", error),
+        }
+    }
+
+    let message = "Embedded character should be valid";
+    let spritesheet = Image::from_bytes(0.0, 0.0, CHARACTER).expect(message);
+
+    Character::from_json(CHARACTER_DEFINITION, spritesheet).expect(message)
+}
+
+// Map files built into the game by file name
+pub fn embedded_map(file: &str) -> Option<&'static str> {
+    match file {
+        "level.map" => Some(MAP),
+        "cave.map" => Some(CAVE),
+        _ => None,
+    }
+}
+
+// Path of a map file named in the level registry
+pub fn map_path(file: &str) -> String {
+    format!("{}/{}", ASSETS_PATH, file)
+}
+
+// Load a level map from disk
+pub fn level_map(file: &str) -> Option<String> {
+    let path = map_path(file);
+
+    if std::path::Path::new(&path).exists() {
+        match std::fs::read_to_string(&path) {
+            Ok(map) => return Some(map),
+            Err(error) => eprintln!("{}", error),
+        }
+    }
+
+    None
+}
+
+// Load the level registry from disk, falling back to the embedded one
+pub fn level_registry() -> World {
+    if std::path::Path::new(LEVELS_PATH).exists() {
+        match World::from_uri(LEVELS_PATH) {
+            Ok(world) => return world,
+            Err(error) => eprintln!("{}", error),
+        }
+    }
+
+    World::from_json(LEVELS).expect("Embedded level registry should be valid")
+}
+
+// Load tile properties from disk, falling back to the embedded table
+pub fn tile_properties() -> Tileset {
+    if std::path::Path::new(TILESET_PATH).exists() {
+        match Tileset::from_uri(TILESET_PATH) {
+            Ok(tileset) => return tileset,
+            Err(error) => eprintln!("{}", error),
+        }
+    }
+
+    Tileset::from_json(TILESET).expect("Embedded tileset should be valid")
+}
+
+// Create player animation state machine, earlier rules take precedence
+pub fn subject_machine() -> StateMachine<Sprite> {
+    let mx = MARGIN_X;
+    let my = MARGIN_Y;
+
+    let left = || Condition::True("left".into());
+    let right = || Condition::True("right".into());
+    let up = || Condition::True("up".into());
+    let down = || Condition::True("down".into());
+    let air = || Condition::Greater("speed".into(), my);
+    let ground = || Condition::AtMost("speed".into(), my);
+
+    let rules = [
+        (
+            Sprite::RunningLeft,
+            vec![Condition::Less("dx".into(), -mx), left(), ground()],
+        ),
+        (
+            Sprite::RunningRight,
+            vec![Condition::Greater("dx".into(), mx), right(), ground()],
+        ),
+        (
+            Sprite::RunningUp,
+            vec![Condition::Less("dy".into(), -my), up(), ground()],
+        ),
+        (
+            Sprite::RunningDown,
+            vec![Condition::Greater("dy".into(), my), down(), ground()],
+        ),
+        (Sprite::AirLeft, vec![air(), left()]),
+        (Sprite::AirRight, vec![air(), right()]),
+        (Sprite::AirUp, vec![air(), up()]),
+        (Sprite::AirDown, vec![air(), down()]),
+        (Sprite::FacingLeft, vec![Condition::Less("dx".into(), -mx)]),
+        (
+            Sprite::FacingRight,
+            vec![Condition::Greater("dx".into(), mx)],
+        ),
+        (Sprite::FacingUp, vec![Condition::Less("dy".into(), -my)]),
+        (
+            Sprite::FacingDown,
+            vec![Condition::Greater("dy".into(), my)],
+        ),
+        (Sprite::AirDown, vec![air()]),
+        (Sprite::FacingDown, vec![]),
+    ];
+
+    let mut machine = StateMachine::new(Sprite::FacingDown);
+    let count = rules.len() as i32;
+
+    for (index, (sprite, conditions)) in rules.into_iter().enumerate() {
+        let mut transition = Transition::any(sprite).priority(count - index as i32);
+        for condition in conditions {
+            transition = transition.when(condition);
+        }
+        machine.add(transition);
+    }
+
+    machine
+}
+
+// Create player sprite outline and drop shadow
+pub fn subject_effect() -> Option<Effect> {
+    let [r, g, b, a] = OUTLINE_COLOR;
+    let outline = Outline::new(Color::new(r, g, b, a), OUTLINE_WIDTH);
+
+    let [r, g, b, a] = SHADOW_COLOR;
+    let offset = Point::new(SHADOW_X as f32, SHADOW_Y as f32);
+    let shadow = Shadow::new(Color::new(r, g, b, a), offset);
+
+    if !OUTLINE && !SHADOW {
+        return None;
+    }
+
+    Some(Effect::new(
+        OUTLINE.then_some(outline),
+        SHADOW.then_some(shadow),
+    ))
+}
+
+// Create a waterfall of a number of tiles at a level position, its palette flows with game time
+pub fn waterfall(x: f32, y: f32, tiles: u32) -> IndexedImage {
+    let tile = IndexedImage::from_bytes(0.0, 0.0, WATERFALL)
+        .expect("Waterfall should contain indexed image data");
+
+    let indices = tile.indices().repeat(tiles as usize);
+    let height = tile.height() * tiles;
+    let palette = tile.palette().to_vec();
+
+    let mut image = IndexedImage::new(x, y, tile.width(), height, indices, palette);
+    image.add_cycle(Cycle::new(
+        WATERFALL_START,
+        WATERFALL_END,
+        WATERFALL_SPEED,
+        false,
+    ));
+
+    image
+}
+
+// Load color grading lookup tables
+pub fn lookup_tables() -> BTreeMap<String, Lut> {
+    let message = "Lookup table should contain valid image data";
+
+    let mut luts = BTreeMap::new();
+
+    luts.insert("dusk".into(), Lut::from_bytes(DUSK).expect(message));
+    luts.insert("sepia".into(), Lut::from_bytes(SEPIA).expect(message));
+    luts.insert(
+        "underwater".into(),
+        Lut::from_bytes(UNDERWATER).expect(message),
+    );
+
+    luts
 }
@@ -0,0 +1,476 @@
+use std::fs;
+
+use crate::canvas;
+use crate::graphics::color::Color;
+use crate::graphics::point::Point;
+use crate::graphics::rectangle::Rectangle;
+use crate::graphics::tile::Tile;
+
+use super::data::{EDITOR_HISTORY, EDITOR_MARGIN, EDITOR_SPEED};
+use super::logic::{constrain_map, respawn_player};
+use super::state::State;
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum Tool {
+    #[default]
+    Paint,
+    Fill,
+    Pick,
+    Erase,
+}
+
+impl Tool {
+    pub fn str(&self) -> &str {
+        match self {
+            Tool::Paint => "paint",
+            Tool::Fill => "fill",
+            Tool::Pick => "pick",
+            Tool::Erase => "erase",
+        }
+    }
+}
+
+// Mouse press in progress with the tool it uses and the cells it started and last passed at
+#[derive(Debug, Copy, Clone)]
+pub struct Drag {
+    pub tool: Tool,
+    pub anchor: (i32, i32),
+    pub last: (i32, i32),
+}
+
+// Tiles of a layer before an edit
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    pub layer: usize,
+    pub tiles: Vec<Tile>,
+}
+
+// Level editor, the camera moves freely while the mouse edits tiles of one layer
+#[derive(Debug, Clone, Default)]
+pub struct Editor {
+    pub active: bool,         // Editor mode
+    pub tool: Tool,           // Left button tool
+    pub tile: u32,            // Palette selection
+    pub layer: usize,         // Edited layer
+    pub cursor: Point,        // Mouse position in the view
+    pub drag: Option<Drag>,   // Mouse press in progress
+    pub path: Option<String>, // Map file saved to
+    pub undo: Vec<Snapshot>,  // Edits to undo, latest last
+    pub redo: Vec<Snapshot>,  // Undone edits, latest last
+}
+
+impl Editor {
+    pub fn new(path: Option<&str>) -> Self {
+        Self {
+            path: path.map(|p| p.into()),
+            ..Self::default()
+        }
+    }
+}
+
+// Enter or leave the editor, playing again restarts the player at the spawn point
+pub fn toggle_editor(state: &mut State) {
+    state.edit.active = !state.edit.active;
+
+    if state.edit.drag.take().is_some() {
+        state.env.redraw_minimap();
+    }
+
+    if state.edit.active {
+        if !state.env.textures.contains_key(&state.edit.tile) {
+            state.edit.tile = state.env.textures.keys().next().copied().unwrap_or(0);
+        }
+        state
+            .conf
+            .status
+            .push(format!("Editing layer {}", layer_name(state)));
+    } else {
+        respawn_player(state);
+    }
+}
+
+// Editor keys: tools, undo and redo, next layer and saving the map
+pub fn edit_key(state: &mut State, key: &str) {
+    match key {
+        "1" | "2" | "3" | "4" => {
+            state.edit.tool = match key {
+                "1" => Tool::Paint,
+                "2" => Tool::Fill,
+                "3" => Tool::Pick,
+                _ => Tool::Erase,
+            };
+            state
+                .conf
+                .status
+                .push(format!("Tool {}", state.edit.tool.str()));
+        }
+        "z" => undo_edit(state),
+        "y" => redo_edit(state),
+        "l" => {
+            state.edit.layer = (state.edit.layer + 1) % state.env.layers.len().max(1);
+            state
+                .conf
+                .status
+                .push(format!("Editing layer {}", layer_name(state)));
+        }
+        "w" => save_map(state),
+        _ => {}
+    }
+}
+
+// Move the camera by the direction keys
+pub fn move_camera(state: &mut State) {
+    state.env.x += state.sub.direction.x * EDITOR_SPEED * state.conf.delta;
+    state.env.y += state.sub.direction.y * EDITOR_SPEED * state.conf.delta;
+
+    constrain_map(state);
+}
+
+// Left presses use the selected tool or pick from the palette, right erases, middle picks
+pub fn press_editor(state: &mut State, button: &str) {
+    if button == "left" {
+        let picked = palette(state)
+            .into_iter()
+            .find(|(_, area)| area.contains(&state.edit.cursor));
+
+        if let Some((id, _)) = picked {
+            state.edit.tile = id;
+            return;
+        }
+    }
+
+    let tool = match button {
+        "left" => state.edit.tool,
+        "right" => Tool::Erase,
+        "middle" => Tool::Pick,
+        _ => return,
+    };
+
+    let cell = match cursor_cell(state) {
+        Some(cell) => cell,
+        None => return,
+    };
+
+    state.edit.drag = Some(Drag {
+        tool,
+        anchor: cell,
+        last: cell,
+    });
+
+    match tool {
+        Tool::Paint => {
+            record_edit(state);
+            state
+                .env
+                .paint(state.edit.layer, cell.0, cell.1, Some(state.edit.tile));
+        }
+        Tool::Erase => {
+            record_edit(state);
+            state.env.paint(state.edit.layer, cell.0, cell.1, None);
+        }
+        Tool::Pick => {
+            if let Some(id) = tile_at(state, cell) {
+                state.edit.tile = id;
+                state.edit.tool = Tool::Paint;
+            }
+            state.edit.drag = None;
+        }
+        Tool::Fill => {}
+    }
+}
+
+// Painting and erasing follow the mouse along the line of cells since the last one
+pub fn drag_editor(state: &mut State) {
+    let (drag, cell) = match (state.edit.drag, cursor_cell(state)) {
+        (Some(drag), Some(cell)) if drag.last != cell => (drag, cell),
+        _ => return,
+    };
+
+    let id = match drag.tool {
+        Tool::Paint => Some(state.edit.tile),
+        Tool::Erase => None,
+        _ => {
+            state.edit.drag = Some(Drag { last: cell, ..drag });
+            return;
+        }
+    };
+
+    for (column, row) in line(drag.last, cell).into_iter().skip(1) {
+        state.env.paint(state.edit.layer, column, row, id);
+    }
+
+    state.edit.drag = Some(Drag { last: cell, ..drag });
+}
+
+// Fills cover the rectangle between the press and release cells, painted strokes end by
+// redrawing the minimap
+pub fn release_editor(state: &mut State) {
+    let drag = match state.edit.drag.take() {
+        Some(drag) => drag,
+        None => return,
+    };
+
+    match drag.tool {
+        Tool::Fill => {
+            record_edit(state);
+            let id = Some(state.edit.tile);
+            state.env.fill(state.edit.layer, drag.anchor, drag.last, id);
+        }
+        Tool::Paint | Tool::Erase => {
+            if state
+                .env
+                .layers
+                .get(state.edit.layer)
+                .is_some_and(|l| l.collides())
+            {
+                state.env.redraw_minimap();
+            }
+        }
+        Tool::Pick => {}
+    }
+}
+
+// Cycle the palette selection
+pub fn scroll_palette(state: &mut State, delta: f32) {
+    let ids = state.env.textures.keys().copied().collect::<Vec<u32>>();
+
+    let index = match ids.iter().position(|&id| id == state.edit.tile) {
+        Some(index) => index as i32,
+        None => return,
+    };
+
+    let step = if delta > 0.0 { -1 } else { 1 };
+    let index = (index + step).rem_euclid(ids.len() as i32);
+
+    state.edit.tile = ids[index as usize];
+}
+
+pub fn undo_edit(state: &mut State) {
+    if let Some(snapshot) = state.edit.undo.pop() {
+        let tiles = state.env.restore(snapshot.layer, snapshot.tiles);
+        state.edit.redo.push(Snapshot { tiles, ..snapshot });
+    }
+}
+
+pub fn redo_edit(state: &mut State) {
+    if let Some(snapshot) = state.edit.redo.pop() {
+        let tiles = state.env.restore(snapshot.layer, snapshot.tiles);
+        state.edit.undo.push(Snapshot { tiles, ..snapshot });
+    }
+}
+
+// Write the layers back to the map file the level was loaded from
+pub fn save_map(state: &mut State) {
+    let path = match &state.edit.path {
+        Some(path) => path,
+        None => {
+            state.conf.status.push(String::from(
+                "Level was not loaded from a map file and cannot be saved",
+            ));
+            return;
+        }
+    };
+
+    let saved = state
+        .env
+        .map()
+        .map_err(|e| e.to_string())
+        .and_then(|map| fs::write(path, map).map_err(|e| e.to_string()));
+
+    match saved {
+        Ok(()) => state.conf.status.push(format!("Saved {}", path)),
+        Err(error) => state.conf.status.push(error),
+    }
+}
+
+// Draw the hovered cell or fill rectangle and the palette with the selection outlined
+pub fn render_editor(state: &mut State, buffer: &mut [u8], width: u32, height: u32) {
+    let white = Color::new(255, 255, 255, 255);
+    let black = Color::new(24, 16, 8, 255);
+
+    let layer = state.env.layers.get(state.edit.layer);
+    let scroll = layer.map_or(Point::new(state.env.x, state.env.y), |l| {
+        l.scroll(state.env.x, state.env.y)
+    });
+
+    if let Some(cell) = cursor_cell(state) {
+        let anchor = match state.edit.drag {
+            Some(drag) if drag.tool == Tool::Fill => drag.anchor,
+            _ => cell,
+        };
+
+        let tw = state.env.tile_width;
+        let th = state.env.tile_height;
+
+        let x = anchor.0.min(cell.0) as f32 * tw - scroll.x;
+        let y = anchor.1.min(cell.1) as f32 * th - scroll.y;
+        let w = (anchor.0 - cell.0).abs() as f32 * tw + tw;
+        let h = (anchor.1 - cell.1).abs() as f32 * th + th;
+
+        render_outline(buffer, width, height, &Rectangle::new(x, y, w, h), &white);
+    }
+
+    let palette = palette(state);
+
+    let back = palette
+        .iter()
+        .map(|(_, area)| *area)
+        .reduce(|a, b| a.union(&b));
+
+    if let Some(back) = back {
+        let back = Rectangle::new(
+            back.x - 1.0,
+            back.y - 1.0,
+            back.width + 2.0,
+            back.height + 2.0,
+        );
+        canvas::rectangle::render(buffer, width, height, &back, &black);
+    }
+
+    for (id, area) in palette {
+        if let Some(image) = state.env.textures.get_mut(&id) {
+            image.set_x_y(area.x, area.y);
+            canvas::image::render(buffer, width, height, image);
+        }
+
+        if id == state.edit.tile {
+            let outline = Rectangle::new(
+                area.x - 1.0,
+                area.y - 1.0,
+                area.width + 2.0,
+                area.height + 2.0,
+            );
+            render_outline(buffer, width, height, &outline, &white);
+        }
+    }
+}
+
+// Palette tiles in id order, in rows along the bottom of the view
+fn palette(state: &State) -> Vec<(u32, Rectangle)> {
+    let margin = EDITOR_MARGIN as f32;
+    let w = state.env.tile_width;
+    let h = state.env.tile_height;
+
+    let columns = (((state.conf.width - margin * 2.0) / (w + 1.0)).floor() as usize).max(1);
+    let rows = state.env.textures.len().div_ceil(columns);
+
+    state
+        .env
+        .textures
+        .keys()
+        .enumerate()
+        .map(|(i, &id)| {
+            let x = margin + (i % columns) as f32 * (w + 1.0);
+            let y = state.conf.height - margin - (rows - i / columns) as f32 * (h + 1.0);
+            (id, Rectangle::new(x, y, w, h))
+        })
+        .collect()
+}
+
+// Level cell under the mouse in the edited layer, none outside the level or over the palette
+fn cursor_cell(state: &State) -> Option<(i32, i32)> {
+    let layer = state.env.layers.get(state.edit.layer)?;
+    let scroll = layer.scroll(state.env.x, state.env.y);
+
+    let over = palette(state)
+        .iter()
+        .any(|(_, area)| area.contains(&state.edit.cursor));
+
+    if over && state.edit.drag.is_none() {
+        return None;
+    }
+
+    let column = ((state.edit.cursor.x + scroll.x) / state.env.tile_width).floor() as i32;
+    let row = ((state.edit.cursor.y + scroll.y) / state.env.tile_height).floor() as i32;
+
+    let (columns, rows) = state.env.size();
+    let inside = (0..columns as i32).contains(&column) && (0..rows as i32).contains(&row);
+
+    inside.then_some((column, row))
+}
+
+fn tile_at(state: &State, cell: (i32, i32)) -> Option<u32> {
+    let layer = state.env.layers.get(state.edit.layer)?;
+
+    let tw = state.env.tile_width;
+    let th = state.env.tile_height;
+    let area = Rectangle::new(cell.0 as f32 * tw, cell.1 as f32 * th, tw, th);
+    let centre = Point::new(area.x + tw / 2.0, area.y + th / 2.0);
+
+    layer
+        .query(&area)
+        .find(|t| t.boundary.contains(&centre))
+        .map(|t| t.id)
+}
+
+fn layer_name(state: &State) -> String {
+    state
+        .env
+        .layers
+        .get(state.edit.layer)
+        .map_or("none".into(), |l| l.name.clone())
+}
+
+// Remember the edited layer before a change, a new change drops the undone ones
+fn record_edit(state: &mut State) {
+    let layer = match state.env.layers.get(state.edit.layer) {
+        Some(layer) => layer,
+        None => return,
+    };
+
+    state.edit.undo.push(Snapshot {
+        layer: state.edit.layer,
+        tiles: layer.tiles().to_vec(),
+    });
+
+    if state.edit.undo.len() > EDITOR_HISTORY {
+        state.edit.undo.remove(0);
+    }
+
+    state.edit.redo.clear();
+}
+
+// Cells of a Bresenham line between two cells, both included
+fn line(from: (i32, i32), to: (i32, i32)) -> Vec<(i32, i32)> {
+    let (dx, dy) = ((to.0 - from.0).abs(), -(to.1 - from.1).abs());
+    let (sx, sy) = ((to.0 - from.0).signum(), (to.1 - from.1).signum());
+
+    let mut cells = vec![from];
+    let (mut x, mut y) = from;
+    let mut error = dx + dy;
+
+    while (x, y) != to {
+        let double = 2 * error;
+        if double >= dy {
+            error += dy;
+            x += sx;
+        }
+        if double <= dx {
+            error += dx;
+            y += sy;
+        }
+        cells.push((x, y));
+    }
+
+    cells
+}
+
+fn render_outline(buffer: &mut [u8], width: u32, height: u32, area: &Rectangle, color: &Color) {
+    let Rectangle {
+        x,
+        y,
+        width: w,
+        height: h,
+    } = *area;
+
+    let sides = [
+        Rectangle::new(x, y, w, 1.0),
+        Rectangle::new(x, y + h - 1.0, w, 1.0),
+        Rectangle::new(x, y, 1.0, h),
+        Rectangle::new(x + w - 1.0, y, 1.0, h),
+    ];
+
+    for side in sides.iter() {
+        canvas::rectangle::render(buffer, width, height, side, color);
+    }
+}
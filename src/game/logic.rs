@@ -1,9 +1,19 @@
+use crate::graphics::color::Color;
+use crate::graphics::graphic::Graphic;
+use crate::graphics::layer::Role;
 use crate::graphics::point::Point;
 use crate::graphics::rectangle::Rectangle;
+use crate::graphics::scene::Transform;
+use crate::graphics::tileset::{Collision, Slope, TileProperties};
+use crate::graphics::tween::{Easing, Tween};
 
 use crate::canvas;
 
-use super::data::Sprite;
+use super::actor::Behaviour;
+use super::data::{BOUNCE, CLIMB, DISSIPATION, HEALTH, HURT, MINIMAP_MARGIN, MINIMAP_SLIDE, SLOPE};
+use super::data::{COIN_COLOR, ENEMY_COLOR, PLATFORM_TILE};
+use super::data::{DOOR_COLOR, TRANSITION, TRANSITION_COLOR};
+use super::editor::{move_camera, render_editor};
 use super::state::State;
 
 // Update state
@@ -16,58 +26,279 @@ pub fn update_state(state: &mut State, time: f32, delta: f32, fps: f32) {
         state.conf.delta = state.conf.max;
     };
 
-    state.conf.accumulator += state.conf.delta;
-
     update_direction(state);
 
-    // Timestepping
-    while state.conf.accumulator > state.conf.step {
-        state.conf.accumulator -= state.conf.step;
-        compute_physics(state); // Linear integration
+    animate_tiles(state);
+    cycle_palettes(state);
+
+    // The game is paused while editing
+    if state.edit.active {
+        move_camera(state);
+    } else {
+        state.conf.accumulator += state.conf.delta;
+
+        // Timestepping
+        while state.conf.accumulator > state.conf.step {
+            state.conf.accumulator -= state.conf.step;
+            compute_physics(state); // Linear integration
+        }
+
+        // Linear interpolation
+        let alpha = state.conf.accumulator / state.conf.step;
+        state.sub.interpolation = interpolate_coordinates(alpha, state);
+
+        state.sub.animations.update(state.conf.delta);
+
+        for event in state.sub.animations.take_events() {
+            if event == "footstep" {
+                step_player(state);
+            }
+        }
+
+        // Leaving the air puts a foot down
+        for event in state.sub.machine.events() {
+            if event.from.airborne() && !event.to.airborne() {
+                step_player(state);
+            }
+        }
+
+        take_exit(state);
+        travel_level(state);
     }
 
-    // Linear interpolation
-    let alpha = state.conf.accumulator / state.conf.step;
-    state.sub.interpolation = interpolate_coordinates(alpha, state);
+    let view = state.view();
+    state.env.minimap.explore(&view);
+
+    state.env.slide.update(state.conf.delta);
+    let y = state.env.minimap.y();
+    state.env.minimap.set_x_y(state.env.slide.value(), y);
+
+    update_grading(state);
 }
 
 // Render graphics
 pub fn render_graphics(state: &mut State, buffer: &mut [u8], width: u32, height: u32) {
-    let message = |id: u32| format!("Texture with id '{}' should exist", id);
-
-    let x = state.sub.interpolation.x - state.env.x;
-    let y = state.sub.interpolation.y - state.env.y;
-
     state.env.background.set_x_y(-state.env.x, -state.env.y);
 
     canvas::image::render(buffer, width, height, &state.env.background);
 
-    for tile in state.env.tiles.iter() {
-        let x = tile.position.x - state.env.x;
-        let y = tile.position.y - state.env.y;
+    render_scenery(state, buffer, width, height);
 
-        if exceeds_viewport(&tile.boundary, &state.view()) {
-            continue;
+    render_layers(state, buffer, width, height, false);
+
+    state.env.decals.set_x_y(-state.env.x, -state.env.y);
+
+    canvas::image::render(buffer, width, height, &state.env.decals);
+
+    render_doors(state, buffer, width, height);
+
+    render_actors(state, buffer, width, height);
+
+    state.sub.scene.transform.translation = state.sub.interpolation;
+
+    let graphics = state.sub.scene.flatten(&state.view());
+
+    let graphics = graphics.iter().collect::<Vec<&Graphic>>();
+
+    canvas::graphics::render(buffer, width, height, &graphics);
+
+    render_subject(state, buffer, width, height);
+
+    render_layers(state, buffer, width, height, true);
+
+    canvas::grading::render(buffer, &state.env.grading);
+
+    if state.conf.minimap || !state.env.slide.finished() {
+        let subject = Rectangle::new(state.sub.x, state.sub.y, state.sub.width, state.sub.height);
+        let view = state.view();
+        canvas::minimap::render(buffer, width, height, &state.env.minimap, &subject, &view);
+    }
+
+    if state.conf.fade.value() > 0.0 {
+        let [r, g, b, a] = TRANSITION_COLOR;
+        let amount = state.conf.fade.value();
+        canvas::background::fade(buffer, &Color::new(r, g, b, a), amount);
+    }
+
+    if state.edit.active {
+        render_editor(state, buffer, width, height);
+    }
+}
+
+// Draw the current frame where the sprite node places it, only copied when it is transformed
+pub fn render_subject(state: &mut State, buffer: &mut [u8], width: u32, height: u32) {
+    let world = match state.sub.scene.world("sprite") {
+        Some(world) => world,
+        None => return,
+    };
+
+    let x = world.translation.x - state.env.x;
+    let y = world.translation.y - state.env.y;
+
+    let local = Transform {
+        translation: Point::new(0.0, 0.0),
+        ..world
+    };
+
+    if local.is_identity() {
+        let frame = state.sub.animations.current_frame(x, y);
+        canvas::image::render(buffer, width, height, frame);
+    } else {
+        let frame = state.sub.animations.current_frame(0.0, 0.0);
+        let mut image = local.image(frame).into_owned();
+        image.set_x_y(image.x() + x, image.y() + y);
+        canvas::image::render(buffer, width, height, &image);
+    }
+}
+
+// Draw palette cycled images at their level positions
+pub fn render_scenery(state: &mut State, buffer: &mut [u8], width: u32, height: u32) {
+    for (position, graphic) in state.env.scenery.iter_mut() {
+        graphic.set_x_y(position.x - state.env.x, position.y - state.env.y);
+        canvas::graphics::render(buffer, width, height, &[graphic]);
+    }
+}
+
+// Draw the doors of the level, other exits are openings in the level itself
+pub fn render_doors(state: &State, buffer: &mut [u8], width: u32, height: u32) {
+    let [r, g, b, a] = DOOR_COLOR;
+    let color = Color::new(r, g, b, a);
+
+    for exit in state.env.exits.iter() {
+        if exit.door {
+            let area = exit.bounds(state.env.tile_width, state.env.tile_height);
+            let area = Rectangle::new(
+                area.x - state.env.x,
+                area.y - state.env.y,
+                area.width,
+                area.height,
+            );
+            canvas::rectangle::render(buffer, width, height, &area, &color);
         }
+    }
+}
 
-        let image = state
-            .env
-            .textures
-            .get_mut(&tile.id)
-            .expect(&message(tile.id));
+// Draw coins, enemies and platforms, triggers are only areas
+pub fn render_actors(state: &mut State, buffer: &mut [u8], width: u32, height: u32) {
+    let [r, g, b, a] = COIN_COLOR;
+    let coin = Color::new(r, g, b, a);
+    let [r, g, b, a] = ENEMY_COLOR;
+    let enemy = Color::new(r, g, b, a);
+
+    let terrain = state.env.tileset.terrain(PLATFORM_TILE).cloned();
+
+    for actor in state.env.actors.iter().filter(|a| a.active) {
+        let area = Rectangle::new(
+            (actor.boundary.x - state.env.x).round(),
+            (actor.boundary.y - state.env.y).round(),
+            actor.boundary.width,
+            actor.boundary.height,
+        );
+
+        let color = match actor.behaviour {
+            Behaviour::Coin(_) => &coin,
+            Behaviour::Enemy(_) => &enemy,
+            Behaviour::Trigger(_) => continue,
+            Behaviour::Platform => {
+                let count = (area.width / state.env.tile_width).ceil().max(1.0) as usize;
+
+                // Platforms are a row of terrain pieces joined to their neighbours
+                for i in 0..count {
+                    let same =
+                        |x: i32, y: i32| y == 0 && (0..count as i32).contains(&(i as i32 + x));
+                    let id = terrain
+                        .as_ref()
+                        .and_then(|t| t.tile(t.mask(same)))
+                        .unwrap_or(PLATFORM_TILE);
+
+                    if let Some(image) = state.env.textures.get_mut(&id) {
+                        image.set_x(area.x + i as f32 * state.env.tile_width);
+                        image.set_y(area.y);
+                        canvas::image::render(buffer, width, height, image);
+                    }
+                }
+
+                continue;
+            }
+        };
 
-        image.set_x(x);
-        image.set_y(y);
+        canvas::rectangle::render(buffer, width, height, &area, color);
+    }
+}
 
-        canvas::image::render(buffer, width, height, image);
+// Render foreground layers or all other layers, scrolled by their parallax factor
+pub fn render_layers(state: &mut State, buffer: &mut [u8], width: u32, height: u32, front: bool) {
+    let message = |id: u32| format!("Texture with id '{}' should exist", id);
+
+    for layer in state.env.layers.iter() {
+        if (layer.role == Role::Foreground) != front || layer.opacity <= 0.0 {
+            continue;
+        }
+
+        let scroll = layer.scroll(state.env.x, state.env.y);
+        let view = Rectangle::new(scroll.x, scroll.y, state.conf.width, state.conf.height);
+
+        for tile in layer.query(&view) {
+            let frame = state.env.animations.get(&tile.id).map_or(0, |a| a.index());
+
+            let flipped = match tile.flip.is_none() {
+                true => None,
+                false => state
+                    .env
+                    .flipped
+                    .get_mut(&(tile.id, tile.flip))
+                    .and_then(|images| images.get_mut(frame)),
+            };
+
+            let image = match (flipped, state.env.animations.get_mut(&tile.id)) {
+                (Some(image), _) => image,
+                (None, Some(animation)) => animation.frame_mut(),
+                (None, None) => state
+                    .env
+                    .textures
+                    .get_mut(&tile.id)
+                    .expect(&message(tile.id)),
+            };
+
+            image.set_x(tile.position.x - scroll.x);
+            image.set_y(tile.position.y - scroll.y);
+
+            canvas::image::render_opacity(buffer, width, height, image, layer.opacity);
+        }
     }
+}
 
-    let frame_x = x + state.sub.offset.x;
-    let frame_y = y + state.sub.offset.y;
+// Advance tile animations by game time, so all tiles of an id show the same frame and keep
+// animating in the editor
+pub fn animate_tiles(state: &mut State) {
+    for animation in state.env.animations.values_mut() {
+        animation.update(state.conf.delta);
+    }
+}
 
-    let frame = state.sub.animations.consecutive_frame(frame_x, frame_y);
+// Rotate the palette cycles of scenery by game time, like tiles they keep cycling in the editor
+pub fn cycle_palettes(state: &mut State) {
+    for (_, graphic) in state.env.scenery.iter_mut() {
+        if let Graphic::Indexed { image } = graphic {
+            image.update(state.conf.time);
+        }
+    }
+}
 
-    canvas::image::render(buffer, width, height, frame);
+// Collision result, deltas and corrections with the properties of the tiles touched
+#[derive(Default)]
+pub struct Contact {
+    pub dx: f32,                         // Delta x
+    pub dy: f32,                         // Delta y
+    pub cx: f32,                         // Correction x
+    pub cy: f32,                         // Correction y
+    pub ground: Option<TileProperties>,  // Tile stood on
+    pub wall: Option<TileProperties>,    // Tile run into
+    pub ceiling: Option<TileProperties>, // Tile bumped into
+    pub damage: f32,                     // Highest damage of touching hazards
+    pub climbable: bool,                 // Overlapping a climbable tile
+    pub platform: bool,                  // Overlapping a one-way platform
+    pub slope: f32,                      // Ground gradient
 }
 
 // Calculate and update physics
@@ -75,42 +306,100 @@ pub fn compute_physics(state: &mut State) {
     let ax = state.sub.force.x / state.sub.mass;
     let ay = state.sub.force.y / state.sub.mass;
 
-    let ix = state.sub.impulse.x * state.sub.direction.x;
+    // Slippery ground gives less grip, keeping the top speed of regular ground
+    let grip = match state.sub.ground.and_then(|g| g.friction) {
+        Some(friction) => (friction / DISSIPATION).min(1.0),
+        None => 1.0,
+    };
+
+    let mut ix = state.sub.impulse.x * state.sub.direction.x * grip;
     let iy = state.sub.impulse.y * state.sub.direction.y;
 
-    state.sub.acceleration.x = (ax + ix) * state.conf.step;
-    state.sub.acceleration.y = (ay + iy + state.env.gravity) * state.conf.step;
+    let gravity = if state.sub.climbing {
+        0.0
+    } else {
+        state.env.gravity
+    };
+
+    // Steep slopes cannot be walked up and pull the player downhill
+    let mut slide = 0.0;
+
+    if state.sub.ground.is_some() && steep_slope(state.sub.slope) {
+        if ix * state.sub.slope < 0.0 {
+            ix = 0.0;
+        }
+        slide = gravity * state.sub.slope / (1.0 + state.sub.slope * state.sub.slope);
+    }
+
+    state.sub.acceleration.x = (ax + ix + slide) * state.conf.step;
+    state.sub.acceleration.y = (ay + iy + gravity) * state.conf.step;
 
     state.sub.velocity.x += state.sub.acceleration.x * state.conf.ratio;
     state.sub.velocity.y += state.sub.acceleration.y * state.conf.ratio;
 
-    let [dx, dy, cx, cy] = collision_delta(state);
+    if state.sub.climbing {
+        state.sub.velocity.y = state.sub.direction.y * CLIMB * state.conf.ratio;
+    }
+
+    let speed = state.sub.velocity.x;
+    let impact = state.sub.velocity.y;
+
+    move_actors(state);
+
+    let contact = collision_delta(state);
+    let Contact { dx, dy, cx, cy, .. } = contact;
 
     state.sub.velocity.x = if cx.abs() < 1.0 { dx } else { 0.0 };
     state.sub.velocity.y = if cy.abs() < 1.0 { dy } else { 0.0 };
 
-    state.sub.velocity.x *= state.env.dissipation.x;
+    // Slippery ground replaces level dissipation and friction
+    let slip = contact
+        .ground
+        .and_then(|g| g.friction)
+        .map(|f| (1.0 - f).powf(state.conf.ratio));
+
+    if slip.is_none() {
+        state.sub.velocity.x *= state.env.dissipation.x;
+    }
     state.sub.velocity.y *= state.env.dissipation.y;
 
     if cx != 0.0 {
         state.sub.velocity.y *= state.env.friction;
     } else if cy != 0.0 {
-        state.sub.velocity.x *= state.env.friction;
+        state.sub.velocity.x *= slip.unwrap_or(state.env.friction);
     } else {
         state.sub.velocity.x *= state.env.resistance;
         state.sub.velocity.y *= state.env.resistance;
     }
 
+    bounce_player(&contact, speed, impact, state);
+
+    state.sub.ground = contact.ground;
+    state.sub.slope = contact.slope;
+
+    let grounded = contact.ground.is_some();
+    let steep = grounded && steep_slope(contact.slope);
+
     state.sub.x += dx;
     state.sub.y += dy;
 
     state.env.x = state.sub.x + state.sub.width / 2.0 - state.conf.width / 2.0;
     state.env.y = state.sub.y + state.sub.height / 2.0 - state.conf.height / 2.0;
 
-    jump_player(cy, state);
+    drop_player(&contact, state);
+
+    climb_player(&contact, state);
+
+    jump_player(grounded && !steep, state);
+
+    land_player(grounded, impact, state);
 
     update_animation(state, dx, dy);
 
+    let damage = touch_actors(state);
+
+    hurt_player(contact.damage.max(damage), state);
+
     constrain_map(state);
 }
 
@@ -153,16 +442,17 @@ pub fn detect_intersection(a: &Rectangle, b: &Rectangle) -> bool {
 }
 
 // Get collision delta
-pub fn collision_delta(state: &State) -> [f32; 4] {
-    let mut cx = 0.0;
-    let mut cy = 0.0;
-    let mut dx = state.sub.velocity.x;
-    let mut dy = state.sub.velocity.y;
+pub fn collision_delta(state: &State) -> Contact {
+    let mut contact = Contact {
+        dx: state.sub.velocity.x,
+        dy: state.sub.velocity.y,
+        ..Contact::default()
+    };
 
     let subject = &Rectangle::new(state.sub.x, state.sub.y, state.sub.width, state.sub.height);
 
     let horizontal = &Rectangle::new(
-        state.sub.x + dx,
+        state.sub.x + contact.dx,
         state.sub.y,
         state.sub.width,
         state.sub.height,
@@ -170,39 +460,287 @@ pub fn collision_delta(state: &State) -> [f32; 4] {
 
     let vertical = &Rectangle::new(
         state.sub.x,
-        state.sub.y + dy,
+        state.sub.y + contact.dy,
         state.sub.width,
         state.sub.height,
     );
 
-    for tile in state.env.tiles.iter() {
-        let rectangle = tile.boundary;
+    // Touching tiles count as hazards, only overlapping tiles can be climbed
+    let touch = &Rectangle::new(
+        state.sub.x - 1.0,
+        state.sub.y - 1.0,
+        state.sub.width + 2.0,
+        state.sub.height + 2.0,
+    );
+
+    // Climbing down passes through one-way platforms as well
+    let dropping = state.sub.drop || state.sub.climbing && state.sub.direction.y > 0.0;
 
-        if exceeds_viewport(&rectangle, &state.view()) {
+    let mut slopes = Vec::new();
+
+    let area = touch.union(horizontal).union(vertical);
+
+    // Moving platforms collide like one-way tiles
+    let platform = TileProperties {
+        collision: Collision::OneWay,
+        ..TileProperties::default()
+    };
+
+    let platforms = state
+        .env
+        .actors
+        .iter()
+        .filter(|a| a.active && matches!(a.behaviour, Behaviour::Platform))
+        .map(|a| (a.boundary, platform));
+
+    let tiles = state
+        .env
+        .collision(&area)
+        .map(|tile| (tile.boundary, state.env.tileset.get(tile.id)))
+        .chain(platforms);
+
+    for (rectangle, properties) in tiles {
+        if properties.hazard() && detect_intersection(&rectangle, touch) {
+            contact.damage = contact.damage.max(properties.damage);
+        }
+
+        if properties.climbable && detect_intersection(&rectangle, subject) {
+            contact.climbable = true;
+        }
+
+        // Slopes are resolved once all other tiles have been
+        if let Some(slope) = properties.slope {
+            if properties.collision != Collision::None {
+                slopes.push((rectangle, properties, slope));
+            }
             continue;
         }
 
+        match properties.collision {
+            Collision::None => continue,
+            Collision::OneWay => {
+                if detect_intersection(&rectangle, vertical) {
+                    contact.platform = true;
+                }
+
+                let above = subject.y + subject.height <= rectangle.y + 0.01;
+
+                if contact.dy > 0.0
+                    && above
+                    && !dropping
+                    && detect_intersection(&rectangle, vertical)
+                {
+                    contact.dy = rectangle.y - subject.y - subject.height;
+                    contact.cy = state.sub.velocity.y - contact.dy;
+                    contact.ground = Some(properties);
+                }
+
+                continue;
+            }
+            Collision::Solid => (),
+        }
+
         if detect_intersection(&rectangle, horizontal) {
-            if dx < 0.0 {
-                dx = rectangle.x + rectangle.width - subject.x;
-            } else if dx > 0.0 {
-                dx = rectangle.x - subject.x - subject.width;
+            if contact.dx < 0.0 {
+                contact.dx = rectangle.x + rectangle.width - subject.x;
+            } else if contact.dx > 0.0 {
+                contact.dx = rectangle.x - subject.x - subject.width;
             }
-            cx = state.sub.velocity.x - dx;
+            contact.cx = state.sub.velocity.x - contact.dx;
+            contact.wall = Some(properties);
         }
 
         if detect_intersection(&rectangle, vertical) {
-            if dy < 0.0 {
-                dy = rectangle.y + rectangle.height - subject.y;
-            } else if dy > 0.0 {
-                dy = rectangle.y - subject.y - subject.height;
+            if contact.dy < 0.0 {
+                contact.dy = rectangle.y + rectangle.height - subject.y;
+                contact.ceiling = Some(properties);
+            } else if contact.dy > 0.0 {
+                contact.dy = rectangle.y - subject.y - subject.height;
+                contact.ground = Some(properties);
             }
-            cy = state.sub.velocity.y - dy;
+            contact.cy = state.sub.velocity.y - contact.dy;
         }
     }
 
-    // Delta x and y, correction x and y
-    [dx, dy, cx, cy]
+    collide_slopes(state, &mut contact, &slopes, dropping);
+
+    snap_ground(state, &mut contact, dropping);
+
+    contact
+}
+
+// Resolve slopes against the subject center once the horizontal move is known,
+// only full height sides and the flat side of a slope block like solid tiles
+pub fn collide_slopes(
+    state: &State,
+    contact: &mut Contact,
+    slopes: &[(Rectangle, TileProperties, Slope)],
+    dropping: bool,
+) {
+    let subject = &Rectangle::new(state.sub.x, state.sub.y, state.sub.width, state.sub.height);
+
+    for (rectangle, properties, slope) in slopes.iter() {
+        let horizontal = &Rectangle::new(
+            subject.x + contact.dx,
+            subject.y,
+            subject.width,
+            subject.height,
+        );
+
+        if !properties.solid() || !detect_intersection(rectangle, horizontal) {
+            continue;
+        }
+
+        // Moving left enters through the right side
+        let right = contact.dx < 0.0;
+
+        let outside = match right {
+            true => subject.x >= rectangle.x + rectangle.width - 0.01,
+            false => subject.x + subject.width <= rectangle.x + 0.01,
+        };
+
+        let edge = slope.edge(rectangle, right);
+        let tolerance = contact.dx.abs() * slope.gradient(rectangle).abs() + 1.0;
+
+        let blocked = match slope.ceiling {
+            true => subject.y < rectangle.y + edge - tolerance,
+            false => subject.y + subject.height > rectangle.y + rectangle.height - edge + tolerance,
+        };
+
+        if contact.dx != 0.0 && outside && edge > 0.0 && blocked {
+            contact.dx = match right {
+                true => rectangle.x + rectangle.width - subject.x,
+                false => rectangle.x - subject.x - subject.width,
+            };
+            contact.cx = state.sub.velocity.x - contact.dx;
+            contact.wall = Some(*properties);
+        }
+    }
+
+    let center = subject.x + contact.dx + subject.width / 2.0;
+    let bottom = subject.y + subject.height;
+
+    for (rectangle, properties, slope) in slopes.iter() {
+        let vertical = &Rectangle::new(
+            subject.x + contact.dx,
+            subject.y + contact.dy,
+            subject.width,
+            subject.height,
+        );
+
+        let solid = properties.solid();
+        let passable = properties.collision == Collision::OneWay && dropping;
+
+        if properties.collision == Collision::OneWay && detect_intersection(rectangle, vertical) {
+            contact.platform = true;
+        }
+
+        let spans = center >= rectangle.x && center < rectangle.x + rectangle.width;
+        let surface = slope.surface(rectangle, center);
+        let tolerance = contact.dx.abs() * slope.gradient(rectangle).abs() + 1.0;
+
+        let top = rectangle.y;
+        let base = rectangle.y + rectangle.height;
+
+        if slope.ceiling {
+            if solid && spans && contact.dy < 0.0 && subject.y + contact.dy < surface {
+                if subject.y >= surface - tolerance {
+                    contact.dy = surface - subject.y;
+                    contact.cy = state.sub.velocity.y - contact.dy;
+                    contact.ceiling = Some(*properties);
+                }
+            } else if !passable
+                && contact.dy > 0.0
+                && bottom <= top + 0.01
+                && detect_intersection(rectangle, vertical)
+            {
+                contact.dy = top - bottom;
+                contact.cy = state.sub.velocity.y - contact.dy;
+                contact.ground = Some(*properties);
+                contact.slope = 0.0;
+            }
+        } else if !passable && spans && bottom + contact.dy > surface {
+            if bottom <= surface + tolerance {
+                contact.dy = surface - bottom;
+                contact.cy = state.sub.velocity.y - contact.dy;
+                contact.ground = Some(*properties);
+                contact.slope = slope.gradient(rectangle);
+            }
+        } else if solid
+            && contact.dy < 0.0
+            && subject.y >= base - 0.01
+            && detect_intersection(rectangle, vertical)
+        {
+            contact.dy = base - subject.y;
+            contact.cy = state.sub.velocity.y - contact.dy;
+            contact.ceiling = Some(*properties);
+        }
+    }
+}
+
+// Keep walking players on descending ground instead of dropping them off every step
+pub fn snap_ground(state: &State, contact: &mut Contact, dropping: bool) {
+    if contact.ground.is_some() || !state.sub.grounded || state.sub.climbing {
+        return;
+    }
+
+    if state.sub.velocity.y < 0.0 {
+        return;
+    }
+
+    let left = state.sub.x + contact.dx;
+    let center = left + state.sub.width / 2.0;
+    let bottom = state.sub.y + state.sub.height + contact.dy;
+
+    let mut nearest: Option<(f32, TileProperties, f32)> = None;
+
+    // Snapping reaches less than the subject height at walking speeds
+    let area = Rectangle::new(left, bottom, state.sub.width, state.sub.height);
+
+    for tile in state.env.collision(&area) {
+        let rectangle = tile.boundary;
+
+        let properties = state.env.tileset.get(tile.id);
+
+        let (surface, gradient) = match (properties.collision, properties.slope) {
+            (Collision::None, _) => continue,
+            (Collision::OneWay, _) if dropping => continue,
+            (_, Some(slope)) if !slope.ceiling => {
+                if center < rectangle.x || center >= rectangle.x + rectangle.width {
+                    continue;
+                }
+                (
+                    slope.surface(&rectangle, center),
+                    slope.gradient(&rectangle),
+                )
+            }
+            _ => {
+                if left >= rectangle.x + rectangle.width || left + state.sub.width <= rectangle.x {
+                    continue;
+                }
+                (rectangle.y, 0.0)
+            }
+        };
+
+        let gap = surface - bottom;
+        let reach = contact.dx.abs() * state.sub.slope.abs().max(gradient.abs()) + 1.0;
+
+        if gap >= 0.0 && gap <= reach && nearest.is_none_or(|(g, _, _)| gap < g) {
+            nearest = Some((gap, properties, gradient));
+        }
+    }
+
+    if let Some((gap, properties, gradient)) = nearest {
+        contact.dy += gap;
+        contact.cy = state.sub.velocity.y - contact.dy;
+        contact.ground = Some(properties);
+        contact.slope = gradient;
+    }
+}
+
+// Slopes steeper than the walkable limit
+pub fn steep_slope(gradient: f32) -> bool {
+    gradient.abs().atan() > SLOPE.to_radians()
 }
 
 // Interpolate subject position
@@ -235,27 +773,6 @@ pub fn constrain_map(state: &mut State) {
     }
 }
 
-// Check if object clips viewport
-pub fn exceeds_viewport(object: &Rectangle, view: &Rectangle) -> bool {
-    if object.x as i32 > (view.x + view.width) as i32 {
-        return true;
-    }
-
-    if view.x as i32 > (object.x + object.width) as i32 {
-        return true;
-    }
-
-    if object.y as i32 > (view.y + view.height) as i32 {
-        return true;
-    }
-
-    if view.y as i32 > (object.y + view.height) as i32 {
-        return true;
-    }
-
-    return false;
-}
-
 // Update subject direction
 pub fn update_direction(state: &mut State) {
     state.sub.direction.x = 0.0;
@@ -279,15 +796,15 @@ pub fn update_direction(state: &mut State) {
 }
 
 // Player jump logic
-pub fn jump_player(overlap: f32, state: &mut State) {
+pub fn jump_player(grounded: bool, state: &mut State) {
     state.sub.contact = false;
 
-    if state.conf.jump && !state.sub.lock && overlap > 0.0 {
+    if state.conf.jump && !state.sub.lock && grounded {
         state.sub.lock = true;
         state.sub.contact = true;
     }
 
-    if !state.conf.jump && state.sub.lock && overlap > 0.0 {
+    if !state.conf.jump && state.sub.lock && grounded {
         state.sub.lock = false;
     }
 
@@ -296,43 +813,272 @@ pub fn jump_player(overlap: f32, state: &mut State) {
     }
 }
 
+// Rebound from bouncy ground and walls, slow impacts settle instead
+pub fn bounce_player(contact: &Contact, speed: f32, impact: f32, state: &mut State) {
+    if let Some(ground) = contact.ground.filter(|g| g.bouncy()) {
+        if impact * ground.restitution > BOUNCE {
+            state.sub.velocity.y = -impact * ground.restitution;
+        }
+    }
+
+    if let Some(wall) = contact.wall.filter(|w| w.bouncy()) {
+        if speed.abs() * wall.restitution > BOUNCE {
+            state.sub.velocity.x = -speed * wall.restitution;
+        }
+    }
+}
+
+// Drop through one-way platforms with down and jump, until the platform is passed
+pub fn drop_player(contact: &Contact, state: &mut State) {
+    let platform = contact
+        .ground
+        .is_some_and(|g| g.collision == Collision::OneWay);
+
+    if state.conf.down && state.conf.jump && !state.sub.lock && platform {
+        state.sub.drop = true;
+        state.sub.lock = true;
+    } else if state.sub.drop && !contact.platform {
+        state.sub.drop = false;
+    }
+}
+
+// Grab climbable tiles with up or down, jumping lets go
+pub fn climb_player(contact: &Contact, state: &mut State) {
+    if !contact.climbable {
+        state.sub.climbing = false;
+    } else if state.conf.jump && state.sub.climbing {
+        state.sub.climbing = false;
+        state.sub.lock = true;
+    } else if state.sub.direction.y != 0.0 && !state.conf.jump {
+        state.sub.climbing = true;
+    }
+}
+
+// Start fading out when the player overlaps an exit, doors are only taken while pressing up
+pub fn take_exit(state: &mut State) {
+    if state.conf.travel.is_some() {
+        return;
+    }
+
+    let subject = Rectangle::new(state.sub.x, state.sub.y, state.sub.width, state.sub.height);
+    let (tw, th) = (state.env.tile_width, state.env.tile_height);
+
+    let target = state
+        .env
+        .exits
+        .iter()
+        .find(|exit| {
+            detect_intersection(&exit.bounds(tw, th), &subject) && (!exit.door || state.conf.up)
+        })
+        .map(|exit| exit.target.clone());
+
+    if let Some(target) = target {
+        let from = state.conf.fade.value();
+        state.conf.fade = Tween::new(from, 1.0, TRANSITION, Easing::QuadIn);
+        state.conf.travel = Some(target);
+    }
+}
+
+// Enter the level of a taken exit once faded out, then fade back in
+pub fn travel_level(state: &mut State) {
+    state.conf.fade.update(state.conf.delta);
+
+    if !state.conf.fade.finished() {
+        return;
+    }
+
+    let target = match state.conf.travel.take() {
+        Some(target) => target,
+        None => return,
+    };
+
+    if let Err(error) = state.enter_level(&target) {
+        eprintln!("{}", error);
+    }
+
+    state.conf.fade = Tween::new(1.0, 0.0, TRANSITION, Easing::QuadOut);
+}
+
+// Move actors along their paths, platforms carry the player standing on them
+pub fn move_actors(state: &mut State) {
+    let subject = Rectangle::new(state.sub.x, state.sub.y, state.sub.width, state.sub.height);
+
+    let mut carry = Point::new(0.0, 0.0);
+
+    for actor in state.env.actors.iter_mut().filter(|a| a.active) {
+        actor.path.update(state.conf.step);
+
+        let position = actor.path.value();
+        let boundary = actor.boundary;
+
+        let riding = state.sub.grounded
+            && (subject.y + subject.height - boundary.y).abs() < 0.5
+            && subject.x < boundary.x + boundary.width
+            && boundary.x < subject.x + subject.width;
+
+        if riding && matches!(actor.behaviour, Behaviour::Platform) {
+            carry = Point::new(position.x - boundary.x, position.y - boundary.y);
+        }
+
+        actor.boundary.x = position.x;
+        actor.boundary.y = position.y;
+    }
+
+    state.sub.x += carry.x;
+    state.sub.y += carry.y;
+}
+
+// Collect touched coins and apply entered triggers, returning the damage of touched enemies
+pub fn touch_actors(state: &mut State) -> f32 {
+    let subject = Rectangle::new(state.sub.x, state.sub.y, state.sub.width, state.sub.height);
+
+    let mut damage: f32 = 0.0;
+
+    for actor in state.env.actors.iter_mut().filter(|a| a.active) {
+        let touching = detect_intersection(&actor.boundary, &subject);
+
+        match &mut actor.behaviour {
+            Behaviour::Coin(value) if touching => {
+                state.sub.coins += *value;
+                actor.active = false;
+            }
+            Behaviour::Enemy(hit) if touching => damage = damage.max(*hit),
+            Behaviour::Trigger(trigger) => {
+                if touching && !trigger.inside {
+                    if let Some(message) = &trigger.message {
+                        state.conf.status.push(message.clone());
+                    }
+                    if let Some(mood) = &trigger.mood {
+                        state.env.mood = mood.clone();
+                    }
+                    actor.active = !trigger.once;
+                }
+                trigger.inside = touching;
+            }
+            _ => {}
+        }
+    }
+
+    damage
+}
+
+// Take damage from hazards with a grace period, running out of health respawns the player
+pub fn hurt_player(damage: f32, state: &mut State) {
+    state.sub.hurt = (state.sub.hurt - state.conf.step).max(0.0);
+
+    if damage <= 0.0 || state.sub.hurt > 0.0 {
+        return;
+    }
+
+    state.sub.health -= damage;
+    state.sub.hurt = HURT;
+
+    if state.sub.health <= 0.0 {
+        respawn_player(state);
+    }
+}
+
+// Put the player back at the spawn point at rest with full health
+pub fn respawn_player(state: &mut State) {
+    state.sub.x = state.sub.spawn.x;
+    state.sub.y = state.sub.spawn.y;
+    state.sub.previous = state.sub.spawn;
+    state.sub.velocity = Point::new(0.0, 0.0);
+    state.sub.health = HEALTH;
+    state.sub.drop = false;
+    state.sub.climbing = false;
+}
+
+// Player landing logic, hard landings leave footprints on the decal layer
+pub fn land_player(grounded: bool, impact: f32, state: &mut State) {
+    if grounded && !state.sub.grounded && impact > state.sub.impact {
+        let color = Color::new(48, 32, 16, 112);
+
+        let x = state.sub.x.round();
+        let y = (state.sub.y + state.sub.height).round();
+        let w = state.sub.width;
+
+        let left = Graphic::rectangle(Rectangle::new(x + 1.0, y, 5.0, 2.0), color);
+        let right = Graphic::rectangle(Rectangle::new(x + w - 6.0, y, 5.0, 2.0), color);
+
+        paint_decals(state, &[&left, &right]);
+    }
+
+    state.sub.grounded = grounded;
+}
+
+// Leave a faint footprint where the running player touches the ground
+pub fn step_player(state: &mut State) {
+    if !state.sub.grounded {
+        return;
+    }
+
+    let color = Color::new(48, 32, 16, 40);
+
+    let x = (state.sub.x + state.sub.width / 2.0).round();
+    let y = (state.sub.y + state.sub.height).round();
+
+    let footprint = Graphic::rectangle(Rectangle::new(x - 2.0, y, 4.0, 1.0), color);
+
+    paint_decals(state, &[&footprint]);
+}
+
+// Draw graphics in level coordinates onto the persistent decal layer
+pub fn paint_decals(state: &mut State, graphics: &[&Graphic]) {
+    canvas::graphics::render_image(&mut state.env.decals, graphics);
+}
+
 // Set player animation
 pub fn update_animation(state: &mut State, dx: f32, dy: f32) {
-    let kr = state.conf.right; // Key right
-    let kl = state.conf.left; // Key left
-    let ku = state.conf.up; // Key up
-    let kd = state.conf.down; // Key down
-    let mx = 0.1; // Margin x
-    let my = 0.1; // Margin y
-    let ir = dy.abs() > my; // In air
-
-    if dx < -mx && kl && !ir {
-        state.sub.animations.set(&Sprite::RunningLeft.str()); // Running left
-    } else if dx > mx && kr && !ir {
-        state.sub.animations.set(&Sprite::RunningRight.str()); // Running right
-    } else if dy < -my && ku && !ir {
-        state.sub.animations.set(&Sprite::RunningUp.str()); // Running up
-    } else if dy > my && kd && !ir {
-        state.sub.animations.set(&Sprite::RunningDown.str()); // Running down
-    } else if ir && kl {
-        state.sub.animations.set(&Sprite::AirLeft.str()); // In air, moving left
-    } else if ir && kr {
-        state.sub.animations.set(&Sprite::AirRight.str()); // In air, moving right
-    } else if ir && ku {
-        state.sub.animations.set(&Sprite::AirUp.str()); // In air, moving up
-    } else if ir && kd {
-        state.sub.animations.set(&Sprite::AirDown.str()); // In air, moving down
-    } else if dx < -mx {
-        state.sub.animations.set(&Sprite::FacingLeft.str()); // Facing left
-    } else if dx > mx {
-        state.sub.animations.set(&Sprite::FacingRight.str()); // Facing right
-    } else if dy < -my {
-        state.sub.animations.set(&Sprite::FacingUp.str()); // Facing up
-    } else if dy > my {
-        state.sub.animations.set(&Sprite::FacingDown.str()); // Facing down
-    } else if ir {
-        state.sub.animations.set(&Sprite::AirDown.str()); // In air
-    } else {
-        state.sub.animations.set(&Sprite::FacingDown.str()); // Inactive
+    let machine = &mut state.sub.machine;
+
+    machine.set_float("dx", dx);
+    machine.set_float("dy", dy);
+    machine.set_float("speed", dy.abs());
+    machine.set_bool("left", state.conf.left);
+    machine.set_bool("right", state.conf.right);
+    machine.set_bool("up", state.conf.up);
+    machine.set_bool("down", state.conf.down);
+
+    machine.update(&mut state.sub.animations);
+}
+
+// Slide minimap in from or out to the right edge of the screen
+pub fn slide_minimap(state: &mut State) {
+    let from = state.env.slide.value();
+
+    let to = match state.conf.minimap {
+        true => state.conf.width - (state.env.minimap.width() + MINIMAP_MARGIN) as f32,
+        false => state.conf.width,
+    };
+
+    let easing = match state.conf.minimap {
+        true => Easing::BackOut,
+        false => Easing::QuadIn,
+    };
+
+    state.env.slide = Tween::new(from, to, MINIMAP_SLIDE, easing);
+}
+
+// Cross-fade color grading of the zone containing the subject
+pub fn update_grading(state: &mut State) {
+    let center = Point::new(
+        state.sub.x + state.sub.width / 2.0,
+        state.sub.y + state.sub.height / 2.0,
+    );
+
+    let name = state
+        .env
+        .zones
+        .iter()
+        .find(|zone| zone.boundary.contains(&center))
+        .map_or(&state.env.mood, |zone| &zone.name);
+
+    if state.env.grading.name() != name {
+        let lut = state.env.luts.get(name).cloned();
+        let name = name.clone();
+        state.env.grading.fade(&name, lut, state.env.fade);
     }
+
+    state.env.grading.update(state.conf.delta);
 }
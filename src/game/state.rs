@@ -1,115 +1,288 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
 
 use crate::display::window::Graphics;
 
-use crate::graphics::animation::Animations;
+use crate::graphics::animation::{Animation, Animations, StateMachine};
+use crate::graphics::asset::{AssetError, Context};
+use crate::graphics::entity::Entity;
+use crate::graphics::graphic::Graphic;
 use crate::graphics::image::Image;
+use crate::graphics::layer::Layer;
+use crate::graphics::level::Level;
+use crate::graphics::lut::{Grading, Lut};
+use crate::graphics::minimap::Minimap;
 use crate::graphics::point::Point;
 use crate::graphics::rectangle::Rectangle;
-use crate::graphics::tile::Tile;
+use crate::graphics::scene::{Node, Transform};
+use crate::graphics::tile::{Flip, MapError, Tile};
+use crate::graphics::tiled::{Map, Object, Property};
+use crate::graphics::tileset::{TileProperties, Tileset};
+use crate::graphics::tween::Tween;
+use crate::graphics::world::{Exit, Target, World, WorldLevel};
 
-use super::data::subject_animations;
-use super::data::{BACKGROUND, ENVIRONMENT, MAP};
-use super::data::{DENSITY, IMPULSE, JUMP, MASS};
+use super::data::{embedded_map, level_registry, map_path};
+use super::data::{level_map, tile_properties, HEALTH};
+use super::data::{lookup_tables, subject_character, subject_effect, subject_machine, Sprite};
+use super::data::{BACKGROUND, ENVIRONMENT, MAP, PATTERN};
+use super::data::{DENSITY, IMPACT, IMPULSE, JUMP, MASS};
 use super::data::{DISSIPATION, FRICTION, GRAVITY, RESISTANCE};
 use super::data::{ENV_HEIGHT, ENV_WIDTH, ENV_X, ENV_Y, TILE_HEIGHT, TILE_WIDTH};
 use super::data::{FPS, HEIGHT, MAX, RATIO, STEP, TITLE, WIDTH};
-use super::data::{PATTERN, SPRITE_X, SPRITE_Y, SUB_HEIGHT, SUB_WIDTH, SUB_X, SUB_Y};
+use super::data::{GRADING, GRADING_FADE, ZONES};
+use super::data::{MINIMAP, MINIMAP_FOG, MINIMAP_MARGIN, MINIMAP_SCALE};
 
-use super::logic::{render_graphics, update_state};
+use super::actor::{spawn_entities, spawn_registry, Actor, Spawn};
+use super::editor::{drag_editor, edit_key, press_editor, release_editor, scroll_palette};
+use super::editor::{toggle_editor, Editor};
+use super::logic::{render_graphics, slide_minimap, update_state};
 
 // State setup
 #[derive(Default)]
 pub struct State {
-    pub conf: Configuration, // Game
-    pub env: Environment,    // Level
-    pub sub: Subject,        // Player
+    pub conf: Configuration,             // Game
+    pub env: Environment,                // Level
+    pub sub: Subject,                    // Player
+    pub edit: Editor,                    // Level editor
+    pub world: World,                    // Level registry
+    pub spawns: BTreeMap<String, Spawn>, // Entity spawn functions by type
 }
 
 // Game setup
 #[derive(Default)]
 pub struct Configuration {
-    pub title: String,    // Window title
-    pub width: f32,       // Window width
-    pub height: f32,      // Window height
-    pub step: f32,        // Frame time step
-    pub ratio: f32,       // Frame time ratio
-    pub delta: f32,       // Frame delta time
-    pub fps: f32,         // Frame count per second
-    pub max: f32,         // Frame max delta time
-    pub time: f32,        // Frame total time
-    pub accumulator: f32, // Frame time accumulator
-    pub up: bool,         // Key up
-    pub down: bool,       // Key down
-    pub left: bool,       // Key left
-    pub right: bool,      // Key right
-    pub jump: bool,       // Key jump
+    pub title: String,          // Window title
+    pub width: f32,             // Window width
+    pub height: f32,            // Window height
+    pub step: f32,              // Frame time step
+    pub ratio: f32,             // Frame time ratio
+    pub delta: f32,             // Frame delta time
+    pub fps: f32,               // Frame count per second
+    pub max: f32,               // Frame max delta time
+    pub time: f32,              // Frame total time
+    pub accumulator: f32,       // Frame time accumulator
+    pub up: bool,               // Key up
+    pub down: bool,             // Key down
+    pub left: bool,             // Key left
+    pub right: bool,            // Key right
+    pub jump: bool,             // Key jump
+    pub map: bool,              // Key map
+    pub editor: bool,           // Key editor
+    pub save: bool,             // Key save
+    pub minimap: bool,          // Minimap visibility
+    pub level: String,          // Level name in the registry
+    pub travel: Option<Target>, // Level an exit is fading out to
+    pub fade: Tween<f32>,       // Level transition darkness
+    pub status: Vec<String>,    // Messages for the player
 }
 
 // Level properties
 #[derive(Default)]
 pub struct Environment {
-    pub x: f32,                         // Level x
-    pub y: f32,                         // Level y
-    pub width: f32,                     // Level width
-    pub height: f32,                    // Level height
-    pub gravity: f32,                   // Level gravity
-    pub friction: f32,                  // Level friction
-    pub resistance: f32,                // Level air resistance
-    pub dissipation: Point,             // Level dissipation
-    pub pattern: Image,                 // Level pattern
-    pub background: Image,              // Level background
-    pub spritesheet: Image,             // Level spritesheet
-    pub textures: BTreeMap<u32, Image>, // Level textures
-    pub tiles: Vec<Tile>,               // Level tiles
+    pub x: f32,                                     // Level x
+    pub y: f32,                                     // Level y
+    pub width: f32,                                 // Level width
+    pub height: f32,                                // Level height
+    pub tile_width: f32,                            // Level tile width
+    pub tile_height: f32,                           // Level tile height
+    pub gravity: f32,                               // Level gravity
+    pub friction: f32,                              // Level friction
+    pub resistance: f32,                            // Level air resistance
+    pub dissipation: Point,                         // Level dissipation
+    pub pattern: Image,                             // Level pattern
+    pub background: Image,                          // Level background
+    pub spritesheet: Image,                         // Level spritesheet
+    pub textures: BTreeMap<u32, Image>,             // Level textures
+    pub animations: BTreeMap<u32, Animation>,       // Level tile animations
+    pub flipped: BTreeMap<(u32, Flip), Vec<Image>>, // Level flipped textures by animation frame
+    pub layers: Vec<Layer>,                         // Level tile layers
+    pub decals: Image,                              // Level decal layer
+    pub scenery: Vec<(Point, Graphic)>,             // Palette cycled images by level position
+    pub luts: BTreeMap<String, Lut>,                // Level color lookup tables
+    pub zones: Vec<Zone>,                           // Level color grading zones
+    pub mood: String,                               // Level default lookup table
+    pub fade: f32,                                  // Level grading fade time
+    pub grading: Grading,                           // Level color grading
+    pub minimap: Minimap,                           // Level minimap
+    pub slide: Tween<f32>,                          // Level minimap slide x
+    pub objects: Vec<Object>,                       // Level map objects
+    pub tileset: Tileset,                           // Level tile properties
+    pub entities: Vec<Entity>,                      // Level map entities
+    pub actors: Vec<Actor>,                         // Level spawned entities
+    pub entries: BTreeMap<String, Point>,           // Level entry points in tiles
+    pub exits: Vec<Exit>,                           // Level exits
+}
+
+// Color grading area
+#[derive(Default)]
+pub struct Zone {
+    pub boundary: Rectangle, // Zone boundary
+    pub name: String,        // Zone lookup table
 }
 
 // Player properties
 #[derive(Default)]
 pub struct Subject {
-    pub x: f32,                 // Player x
-    pub y: f32,                 // Player y
-    pub width: f32,             // Player width
-    pub height: f32,            // Player heigth
-    pub force: Point,           // Player force
-    pub velocity: Point,        // Player velocity
-    pub acceleration: Point,    // Player acceleration
-    pub direction: Point,       // Player direction
-    pub previous: Point,        // Player previous
-    pub impulse: Point,         // Player impulse
-    pub interpolation: Point,   // Player interpolation
-    pub mass: f32,              // Player mass
-    pub density: f32,           // Player density
-    pub jump: f32,              // Player jump impulse
-    pub contact: bool,          // Player contact
-    pub lock: bool,             // Player lock
-    pub spritesheet: Image,     // Player spritesheet
-    pub animations: Animations, // Player animations
-    pub offset: Point,          // Player offset
+    pub x: f32,                         // Player x
+    pub y: f32,                         // Player y
+    pub width: f32,                     // Player width
+    pub height: f32,                    // Player heigth
+    pub force: Point,                   // Player force
+    pub velocity: Point,                // Player velocity
+    pub acceleration: Point,            // Player acceleration
+    pub direction: Point,               // Player direction
+    pub previous: Point,                // Player previous
+    pub impulse: Point,                 // Player impulse
+    pub interpolation: Point,           // Player interpolation
+    pub mass: f32,                      // Player mass
+    pub density: f32,                   // Player density
+    pub jump: f32,                      // Player jump impulse
+    pub contact: bool,                  // Player contact
+    pub lock: bool,                     // Player lock
+    pub grounded: bool,                 // Player ground contact
+    pub impact: f32,                    // Player landing impact for footprints
+    pub health: f32,                    // Player health
+    pub coins: u32,                     // Player collected coin value
+    pub hurt: f32,                      // Player time left without taking damage
+    pub spawn: Point,                   // Player respawn point
+    pub drop: bool,                     // Player dropping through a one-way platform
+    pub climbing: bool,                 // Player climbing
+    pub ground: Option<TileProperties>, // Player ground tile
+    pub slope: f32,                     // Player ground gradient
+    pub spritesheet: Image,             // Player spritesheet
+    pub animations: Animations,         // Player animations
+    pub machine: StateMachine<Sprite>,  // Player animation states
+    pub scene: Node,                    // Player scene graph
 }
 
 impl State {
     pub fn new() -> Self {
-        Self {
+        let mut state = Self {
             conf: Configuration::new(),
-            env: Environment::new(),
+            env: Environment::default(),
             sub: Subject::new(),
-        }
+            edit: Editor::default(),
+            world: level_registry(),
+            spawns: spawn_registry(),
+        };
+
+        let start = state.world.start.clone();
+        state
+            .enter_level(&start)
+            .expect("Start level should be valid");
+        state
     }
     pub fn build() -> Self {
         let mut state = Self::new();
         state.init();
         state
     }
+    // Level from a Tiled map, objects are spawned as entities of their class or name so the
+    // player starts at an object of class or name spawn
+    pub fn from_tiled(uri: &str) -> Result<Self, AssetError> {
+        let mut state = Self::new();
+        state.env = Environment::from_tiled(uri)?;
+        state.env.start(state.conf.ratio);
+        state.edit = Editor::new(None);
+        state.world = World::default();
+        state.conf.level = String::new();
+
+        spawn_entities(&mut state);
+
+        if let Some(&entry) = state.env.entries.get("start") {
+            state.place_player(entry);
+        }
+
+        state.init();
+        Ok(state)
+    }
+    // Rebuild the level of a registry entry, spawn its entities and place the player at the
+    // entry point, the player keeps everything else
+    pub fn enter_level(&mut self, target: &Target) -> Result<(), AssetError> {
+        let level = match self.world.level(&target.level) {
+            Some(level) => level.clone(),
+            None => {
+                return Err(AssetError::format(
+                    "Level registry",
+                    format!("level {} is not registered", target.level),
+                ))
+            }
+        };
+
+        let mut env = Environment::from_level(&level)?;
+        env.start(self.conf.ratio);
+        let previous = std::mem::replace(&mut self.env, env);
+
+        // Registry entries and exits come first, spawn entities of the map can replace entries
+        self.env.entries = level.entries.clone();
+        self.env.exits = level.exits.clone();
+
+        spawn_entities(self);
+
+        let entry = match self.env.entries.get(&target.entry) {
+            Some(&entry) => entry,
+            None => {
+                self.env = previous;
+                return Err(AssetError::format(
+                    "Level registry",
+                    format!("level {} has no entry {}", target.level, target.entry),
+                ));
+            }
+        };
+
+        self.check_entries(&target.level);
+
+        self.edit = Editor::new(Some(&map_path(&level.map)));
+        self.conf.level = target.level.clone();
+
+        if !self.conf.minimap {
+            self.env.slide = Tween::still(self.conf.width);
+        }
+
+        self.place_player(entry);
+
+        Ok(())
+    }
+    // Report ways into the entered level that arrive at none of its entries, from the registry
+    // or from exit entities of the level itself
+    fn check_entries(&self, level: &str) {
+        let mut entries = self
+            .world
+            .targets(level)
+            .into_iter()
+            .map(|target| target.entry.clone())
+            .collect::<BTreeSet<String>>();
+
+        for exit in self.env.exits.iter().filter(|e| e.target.level == level) {
+            entries.insert(exit.target.entry.clone());
+        }
+
+        for entry in entries
+            .iter()
+            .filter(|e| !self.env.entries.contains_key(*e))
+        {
+            let message = format!("level {} has no entry {}", level, entry);
+            eprintln!("{}", AssetError::format("Level registry", message));
+        }
+    }
+    // Put the player at an entry point in tiles and respawn there
+    fn place_player(&mut self, entry: Point) {
+        let position = Point::new(
+            entry.x * self.env.tile_width,
+            entry.y * self.env.tile_height,
+        );
+
+        self.sub.x = position.x;
+        self.sub.y = position.y;
+        self.sub.previous = position;
+        self.sub.interpolation = position;
+        self.sub.spawn = position;
+    }
     pub fn view(&self) -> Rectangle {
         Rectangle::new(self.env.x, self.env.y, self.conf.width, self.conf.height)
     }
     fn init(&mut self) {
         self.sub.mass = self.sub.width * self.sub.height * self.sub.density;
-        self.env.friction = (1.0 - self.env.friction).powf(self.conf.ratio);
-        self.env.resistance = (1.0 - self.env.resistance).powf(self.conf.ratio);
-        self.env.dissipation.x = (1.0 - self.env.dissipation.x).powf(self.conf.ratio);
-        self.env.dissipation.y = (1.0 - self.env.dissipation.y).powf(self.conf.ratio);
     }
 }
 
@@ -131,31 +304,259 @@ impl Configuration {
             down: false,
             left: false,
             jump: false,
+            map: false,
+            editor: false,
+            save: false,
+            minimap: MINIMAP,
+            level: String::new(),
+            travel: None,
+            fade: Tween::still(0.0),
+            status: Vec::new(),
         }
     }
 }
 
 impl Environment {
     pub fn new() -> Self {
+        let columns = ENV_WIDTH / TILE_WIDTH;
+        let rows = ENV_HEIGHT / TILE_HEIGHT;
+
+        Self::from_map(MAP, columns, rows).expect("Map should be a valid level")
+    }
+    // Level of the registry read from disk, a broken map file falls back to the built in one
+    pub fn from_level(level: &WorldLevel) -> Result<Self, AssetError> {
+        let (columns, rows) = level
+            .size
+            .unwrap_or((ENV_WIDTH / TILE_WIDTH, ENV_HEIGHT / TILE_HEIGHT));
+
+        let embedded = embedded_map(&level.map);
+
+        if let Some(map) = level_map(&level.map) {
+            match Self::from_map(&map, columns, rows) {
+                Ok(environment) => return Ok(environment),
+                Err(error) if embedded.is_none() => {
+                    return Err(AssetError::new("Level map", error.into()))
+                }
+                Err(error) => eprintln!("{}", error),
+            }
+        }
+
+        match embedded {
+            Some(map) => Self::from_map(map, columns, rows).context("Level map"),
+            None => Err(AssetError::format(
+                "Level registry",
+                format!("map {} of level {} is missing", level.map, level.name),
+            )),
+        }
+    }
+    // Level of a map with a size in tiles, checked against the tiles of the spritesheet
+    pub fn from_map(map: &str, columns: u32, rows: u32) -> Result<Self, MapError> {
         let fb = Image::from_bytes;
         let st = Image::sprite_to_texture_map;
-        let mt = Tile::map_to_tiles;
 
         let message = "Sprite should contain valid image data";
-        let indices = &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15];
 
         let pattern = fb(0.0, 0.0, PATTERN).expect(message);
         let background = fb(0.0, 0.0, BACKGROUND).expect(message);
         let mut spritesheet = fb(0.0, 0.0, ENVIRONMENT).expect(message);
 
-        let textures = st(&mut spritesheet, TILE_WIDTH, TILE_HEIGHT, indices);
-        let tiles = mt(MAP, TILE_WIDTH, TILE_HEIGHT);
+        let count = (spritesheet.width() / TILE_WIDTH) * (spritesheet.height() / TILE_HEIGHT);
+        let indices = (0..count).collect::<Vec<u32>>();
+
+        let textures = st(&mut spritesheet, TILE_WIDTH, TILE_HEIGHT, &indices);
+
+        Layer::validate_map(map, columns, rows, |id| textures.contains_key(&id))?;
+        let layers = Layer::map_to_layers(map, TILE_WIDTH, TILE_HEIGHT)?;
+        let entities = Entity::map_to_entities(map)?;
+
+        let size = (
+            columns * TILE_WIDTH,
+            rows * TILE_HEIGHT,
+            TILE_WIDTH,
+            TILE_HEIGHT,
+        );
+
+        let tileset = tile_properties();
+
+        let mut environment = Self::from_layers(
+            pattern,
+            background,
+            spritesheet,
+            textures,
+            layers,
+            tileset,
+            size,
+        );
+
+        environment.entities = entities;
+
+        Ok(environment)
+    }
+    // Level from a Tiled map, tileset images are resolved relative to the map file
+    pub fn from_tiled(uri: &str) -> Result<Self, AssetError> {
+        let fb = Image::from_bytes;
+
+        let message = "Sprite should contain valid image data";
+
+        let pattern = fb(0.0, 0.0, PATTERN).expect(message);
+        let background = fb(0.0, 0.0, BACKGROUND).expect(message);
+
+        let map = Map::from_uri(uri)?;
+        let directory = Path::new(uri).parent().unwrap_or(Path::new(""));
+
+        let textures = map.textures(directory)?;
+        let layers = map.level_layers()?;
+
+        let mut tiles = layers.iter().flat_map(|l| l.tiles().iter());
+
+        if let Some(tile) = tiles.find(|t| !textures.contains_key(&t.id)) {
+            return Err(AssetError::format(
+                "Tiled map",
+                format!("tile {} is not part of any tileset", tile.id),
+            ));
+        }
+
+        let mut properties = Tileset::new();
+
+        for tileset in map.tilesets.iter() {
+            for (id, tile) in tileset.tiles.iter() {
+                let tile = TileProperties::from_properties(tile)?;
+                properties.set(tileset.first + id, tile);
+            }
+        }
+
+        let size = (
+            map.pixel_width(),
+            map.pixel_height(),
+            map.tile_width,
+            map.tile_height,
+        );
+
+        let mut environment = Self::from_layers(
+            pattern,
+            background,
+            Image::default(),
+            textures,
+            layers,
+            properties,
+            size,
+        );
+
+        environment.objects = map.objects().into_iter().cloned().collect();
+        environment.entities = environment
+            .objects
+            .iter()
+            .map(|o| Entity::from_object(o, map.tile_width as f32, map.tile_height as f32))
+            .collect();
+
+        Ok(environment)
+    }
+    fn from_layers(
+        pattern: Image,
+        background: Image,
+        spritesheet: Image,
+        mut textures: BTreeMap<u32, Image>,
+        mut layers: Vec<Layer>,
+        tileset: Tileset,
+        size: (u32, u32, u32, u32),
+    ) -> Self {
+        let (width, height, tile_width, tile_height) = size;
+
+        // Slope tiles are drawn as their heightfield
+        for (id, texture) in textures.iter_mut() {
+            if let Some(slope) = tileset.get(*id).slope {
+                *texture = slope.mask(texture);
+            }
+        }
+
+        // Animated tiles show their first frame wherever a still image is used
+        let mut animations = BTreeMap::new();
+
+        for (id, definition) in tileset.animations.iter() {
+            let slope = tileset.get(*id).slope;
+
+            let frames = definition
+                .frames
+                .iter()
+                .filter_map(|frame| Some((*frame, textures.get(frame)?)))
+                .map(|(frame, texture)| match slope {
+                    Some(slope) => (frame, slope.mask(texture)),
+                    None => (frame, texture.clone()),
+                })
+                .collect();
+
+            match definition.animation(&frames) {
+                Some(animation) => {
+                    textures.insert(*id, animation.frame().clone());
+                    animations.insert(*id, animation);
+                }
+                None => eprintln!("Animation of tile {:02x} refers to a missing tile", id),
+            }
+        }
+
+        for layer in layers.iter_mut() {
+            layer.autotile(&tileset);
+        }
+
+        // Flipped tiles are drawn from images flipped once, one for every animation frame
+        let mut flipped = BTreeMap::new();
+
+        for tile in layers.iter().flat_map(|l| l.tiles().iter()) {
+            if tile.flip.is_none() || flipped.contains_key(&(tile.id, tile.flip)) {
+                continue;
+            }
+
+            let images = match animations.get(&tile.id) {
+                Some(animation) => animation
+                    .frames()
+                    .iter()
+                    .map(|frame| tile.flip.apply(&frame.image))
+                    .collect(),
+                None => textures
+                    .get(&tile.id)
+                    .map(|texture| vec![tile.flip.apply(texture)])
+                    .unwrap_or_default(),
+            };
+
+            flipped.insert((tile.id, tile.flip), images);
+        }
+
+        let columns = width / tile_width;
+        let rows = height / tile_height;
+
+        let mut minimap = Minimap::new(columns, rows, MINIMAP_SCALE, tile_width, tile_height);
+        let minimap_x = match MINIMAP {
+            true => (WIDTH - minimap.width() - MINIMAP_MARGIN) as f32,
+            false => WIDTH as f32,
+        };
+        minimap.set_x_y(minimap_x, MINIMAP_MARGIN as f32);
+        minimap.set_fog(MINIMAP_FOG);
+        let solid = layers
+            .iter()
+            .filter(|l| l.collides())
+            .flat_map(|l| l.tiles().iter().cloned())
+            .collect::<Vec<Tile>>();
+
+        minimap.rasterize(&solid, &textures);
+
+        let luts = lookup_tables();
+        let zones = ZONES
+            .iter()
+            .map(|&(x, y, width, height, name)| {
+                Zone::new(Rectangle::from_tuple(&(x, y, width, height)), name)
+            })
+            .collect();
+
+        let mut grading = Grading::new();
+        grading.set(GRADING, luts.get(GRADING).cloned());
 
         Self {
             x: ENV_X as f32,
             y: ENV_Y as f32,
-            width: ENV_WIDTH as f32,
-            height: ENV_HEIGHT as f32,
+            width: width as f32,
+            height: height as f32,
+            tile_width: tile_width as f32,
+            tile_height: tile_height as f32,
             gravity: GRAVITY,
             friction: FRICTION,
             resistance: RESISTANCE,
@@ -167,23 +568,173 @@ impl Environment {
             background,
             spritesheet,
             textures,
-            tiles,
+            animations,
+            flipped,
+            layers,
+            decals: Image::blank(0.0, 0.0, width, height),
+            scenery: Vec::new(),
+            luts,
+            zones,
+            mood: GRADING.into(),
+            fade: GRADING_FADE,
+            grading,
+            minimap,
+            slide: Tween::still(minimap_x),
+            objects: Vec::new(),
+            tileset,
+            entities: Vec::new(),
+            actors: Vec::new(),
+            entries: BTreeMap::new(),
+            exits: Vec::new(),
+        }
+    }
+    // Turn friction, resistance and dissipation into factors per physics step
+    pub fn start(&mut self, ratio: f32) {
+        self.friction = (1.0 - self.friction).powf(ratio);
+        self.resistance = (1.0 - self.resistance).powf(ratio);
+        self.dissipation.x = (1.0 - self.dissipation.x).powf(ratio);
+        self.dissipation.y = (1.0 - self.dissipation.y).powf(ratio);
+    }
+    // Change a tile of a layer at runtime, terrain tiles around it are autotiled. The minimap is
+    // left as is, so a stroke of painted cells redraws it once with redraw_minimap when it ends
+    pub fn paint(&mut self, layer: usize, column: i32, row: i32, id: Option<u32>) {
+        self.write(layer, (column, row), (column, row), id);
+    }
+    // Change the tiles of a layer between two corner cells, both included
+    pub fn fill(&mut self, layer: usize, from: (i32, i32), to: (i32, i32), id: Option<u32>) {
+        if self.write(layer, from, to, id) {
+            self.redraw_minimap();
+        }
+    }
+    // Swap the tiles of a layer for earlier ones, returning the replaced tiles
+    pub fn restore(&mut self, layer: usize, tiles: Vec<Tile>) -> Vec<Tile> {
+        let layer = match self.layers.get_mut(layer) {
+            Some(layer) => layer,
+            None => return tiles,
+        };
+
+        let replaced = layer.set_tiles(tiles);
+
+        if layer.collides() {
+            self.redraw_minimap();
+        }
+
+        replaced
+    }
+    // Rasterize the collision layers to the minimap
+    pub fn redraw_minimap(&mut self) {
+        let solid = self.collision_tiles();
+        self.minimap.rasterize(&solid, &self.textures);
+    }
+    // Change tiles of a layer, returning whether a collision layer changed
+    fn write(&mut self, layer: usize, from: (i32, i32), to: (i32, i32), id: Option<u32>) -> bool {
+        if id.is_some_and(|id| !self.textures.contains_key(&id)) {
+            return false;
+        }
+
+        let size = (self.tile_width, self.tile_height);
+
+        let layer = match self.layers.get_mut(layer) {
+            Some(layer) => layer,
+            None => return false,
+        };
+
+        layer.fill(from, to, id, size, &self.tileset);
+        layer.collides()
+    }
+    // Level size in tiles
+    pub fn size(&self) -> (u32, u32) {
+        let columns = (self.width / self.tile_width).round() as u32;
+        let rows = (self.height / self.tile_height).round() as u32;
+
+        (columns, rows)
+    }
+    // Layers and entities in the map text format, normalized like Tile::tiles_to_map
+    pub fn map(&self) -> Result<String, MapError> {
+        let (columns, rows) = self.size();
+
+        let mut map = Layer::layers_to_map(&self.layers, columns, rows)?;
+
+        if !self.entities.is_empty() {
+            map.push('\n');
+            map.push_str(&Entity::entities_to_map(&self.entities));
+        }
+
+        Ok(map)
+    }
+    // Layers, objects and physics of the level for JSON or RON export, friction and resistance
+    // become per step factors once the game starts so their configured values are exported
+    pub fn level(&self) -> Level {
+        let (columns, rows) = self.size();
+        let (width, height) = (self.tile_width as u32, self.tile_height as u32);
+
+        let mut level = Level::new(columns, rows, width, height);
+
+        let metadata = [
+            ("gravity", decimal(self.gravity)),
+            ("friction", decimal(FRICTION)),
+            ("resistance", decimal(RESISTANCE)),
+            ("mood", Property::String(self.mood.clone())),
+        ];
+
+        level.metadata = metadata.into_iter().map(|(k, v)| (k.into(), v)).collect();
+
+        for layer in self.layers.iter() {
+            level.add_layer(layer);
+        }
+        for object in self.objects.iter() {
+            level.add_object(object);
+        }
+
+        level.entities = self.entities.clone();
+
+        level
+    }
+    // Copies of all tiles of the layers that collide with the player
+    fn collision_tiles(&self) -> Vec<Tile> {
+        self.layers
+            .iter()
+            .filter(|l| l.collides())
+            .flat_map(|l| l.tiles().iter().cloned())
+            .collect()
+    }
+    // Tiles of the layers that collide with the player near an area
+    pub fn collision<'a>(&'a self, area: &'a Rectangle) -> impl Iterator<Item = &'a Tile> {
+        self.layers
+            .iter()
+            .filter(|l| l.collides())
+            .flat_map(move |l| l.query(area))
+    }
+}
+
+impl Zone {
+    pub fn new(boundary: Rectangle, name: &str) -> Self {
+        Self {
+            boundary,
+            name: name.into(),
         }
     }
 }
 
 impl Subject {
     pub fn new() -> Self {
-        let x = SUB_X as f32;
-        let y = SUB_Y as f32;
-        let width = SUB_WIDTH as f32;
-        let height = SUB_HEIGHT as f32;
+        let x = 0.0;
+        let y = 0.0;
 
-        let offset = Point::new(SPRITE_X as f32, SPRITE_Y as f32);
+        let character = subject_character();
 
-        let (spritesheet, mut animations) = subject_animations();
+        let width = character.collision.width;
+        let height = character.collision.height;
+        let spritesheet = character.spritesheet;
 
-        animations.set("run_right");
+        let mut animations = character.animations;
+
+        animations.set(&character.initial);
+        animations.set_effect(subject_effect());
+
+        let mut scene = Node::new("subject", Graphic::none(), Transform::translation(x, y));
+        let sprite = Node::new("sprite", Graphic::none(), Transform::identity());
+        scene.add(sprite);
 
         Self {
             x,
@@ -202,9 +753,20 @@ impl Subject {
             jump: JUMP,
             contact: false,
             lock: false,
+            grounded: false,
+            impact: IMPACT,
+            health: HEALTH,
+            coins: 0,
+            hurt: 0.0,
+            spawn: Point::new(x, y),
+            drop: false,
+            climbing: false,
+            ground: None,
+            slope: 0.0,
             spritesheet,
             animations,
-            offset,
+            machine: subject_machine(),
+            scene,
         }
     }
 }
@@ -217,13 +779,60 @@ impl Graphics for State {
             "e" => self.conf.up = active,
             "d" => self.conf.down = active,
             "j" => self.conf.jump = active,
+            "m" => {
+                if active && !self.conf.map {
+                    self.conf.minimap = !self.conf.minimap;
+                    slide_minimap(self);
+                }
+                self.conf.map = active;
+            }
+            "t" => {
+                if active && !self.conf.editor {
+                    toggle_editor(self);
+                }
+                self.conf.editor = active;
+            }
+            "w" => {
+                if active && !self.conf.save && self.edit.active {
+                    edit_key(self, key);
+                }
+                self.conf.save = active;
+            }
+            _ if active && self.edit.active => edit_key(self, key),
+            _ => {}
+        }
+    }
+    fn pointer(&mut self, x: f32, y: f32) {
+        self.edit.cursor = Point::new(x, y);
+
+        if self.edit.active {
+            drag_editor(self);
+        }
+    }
+    fn click(&mut self, active: bool, button: &str) {
+        match (self.edit.active, active) {
+            (true, true) => press_editor(self, button),
+            (true, false) => release_editor(self),
             _ => {}
         }
     }
+    fn wheel(&mut self, delta: f32) {
+        if self.edit.active {
+            scroll_palette(self, delta);
+        }
+    }
     fn update(&mut self, time: f32, delta: f32, fps: f32) {
         update_state(self, time, delta, fps);
     }
     fn render(&mut self, buffer: &mut [u8], width: u32, height: u32) {
         render_graphics(self, buffer, width, height);
     }
+    fn status(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.conf.status)
+    }
+}
+
+// Property of the shortest decimal that reads back as the value, so 0.05 stays 0.05
+fn decimal(value: f32) -> Property {
+    Property::Float(value.to_string().parse().unwrap_or_default())
 }
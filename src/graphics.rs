@@ -1,7 +1,24 @@
 pub mod animation;
+pub mod aseprite;
+pub mod asset;
+pub mod autotile;
+pub mod character;
 pub mod color;
+pub mod effect;
+pub mod entity;
 pub mod graphic;
+pub mod grid;
 pub mod image;
+pub mod indexed;
+pub mod layer;
+pub mod level;
+pub mod lut;
+pub mod minimap;
 pub mod point;
 pub mod rectangle;
+pub mod scene;
 pub mod tile;
+pub mod tiled;
+pub mod tileset;
+pub mod tween;
+pub mod world;
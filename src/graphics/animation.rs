@@ -1,8 +1,14 @@
 use std::collections::BTreeMap;
-use std::thread;
-use std::time::{Duration, Instant};
+use std::fmt::Display;
 
+use serde::Deserialize;
+
+use super::effect::Effect;
 use super::image::Image;
+use super::point::Point;
+use super::rectangle::Rectangle;
+
+pub type Callback = fn(&Animation);
 
 #[derive(Debug, Clone, Default)]
 pub struct Animations {
@@ -23,8 +29,18 @@ impl Animations {
     pub fn remove(&mut self, name: &str) {
         self.map.remove(name);
     }
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    // Switch animation, restarting it unless it is already playing
     pub fn set(&mut self, name: &str) {
+        if self.name == name {
+            return;
+        }
         self.name = name.into();
+        if let Some(animation) = self.map.get_mut(name) {
+            animation.restart();
+        }
     }
     pub fn get(&self) -> Option<&Animation> {
         self.map.get(&self.name)
@@ -41,44 +57,159 @@ impl Animations {
     pub fn get_names(&self) -> Vec<&String> {
         self.map.keys().collect::<Vec<&String>>()
     }
-    pub fn consecutive_frame(&mut self, x: f32, y: f32) -> &Image {
-        let animation = self.get_mut().unwrap();
-
-        animation.set_active(true);
-        animation.consecutive();
+    pub fn set_effect(&mut self, effect: Option<Effect>) {
+        for animation in self.map.values_mut() {
+            animation.set_effect(effect);
+        }
+    }
+    pub fn set_speed(&mut self, speed: f32) {
+        for animation in self.map.values_mut() {
+            animation.set_speed(speed);
+        }
+    }
+    // Advance the current animation by game time
+    pub fn update(&mut self, delta: f32) {
+        if let Some(animation) = self.get_mut() {
+            animation.update(delta);
+        }
+    }
+    // Frame image placed so its pivot lands on x, y
+    pub fn current_frame(&mut self, x: f32, y: f32) -> &Image {
+        let animation = self.get_mut().expect("Current animation should exist");
 
+        let origin = animation.origin();
+        let offset = animation.offset();
+        let pivot = animation.pivot();
         let frame = animation.frame_mut();
 
-        frame.set_x_y(x, y);
+        frame.set_x_y(
+            x + origin.x + offset.x - pivot.x,
+            y + origin.y + offset.y - pivot.y,
+        );
 
         frame
     }
+    pub fn current(&self) -> Option<&Frame> {
+        self.get().map(|a| a.current())
+    }
+    pub fn hitboxes(&self, position: Point) -> Vec<Hitbox> {
+        self.get().map_or(Vec::new(), |a| a.hitboxes(position))
+    }
+    pub fn hurtboxes(&self, position: Point) -> Vec<Hitbox> {
+        self.get().map_or(Vec::new(), |a| a.hurtboxes(position))
+    }
+    // Event markers of frames entered by the current animation since the last call
+    pub fn take_events(&mut self) -> Vec<String> {
+        self.get_mut().map_or(Vec::new(), |a| a.take_events())
+    }
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Mode {
+    #[default]
+    Loop,
+    Once,
+    PingPong,
+    Reverse,
+}
+
+// Named box in frame image coordinates
+#[derive(Debug, Clone, Default)]
+pub struct Hitbox {
+    pub name: String,
+    pub bounds: Rectangle,
+}
+
+impl Hitbox {
+    pub fn new(name: &str, bounds: Rectangle) -> Self {
+        Self {
+            name: name.into(),
+            bounds,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Frame {
+    pub image: Image,
+    pub duration: f32,
+    pub offset: Point,
+    pub pivot: Point,
+    pub hitboxes: Vec<Hitbox>,
+    pub hurtboxes: Vec<Hitbox>,
+    pub events: Vec<String>,
+}
+
+impl Frame {
+    pub fn new(image: Image, duration: f32) -> Self {
+        Self {
+            image,
+            duration,
+            offset: Point::new(0.0, 0.0),
+            pivot: Point::new(0.0, 0.0),
+            hitboxes: Vec::new(),
+            hurtboxes: Vec::new(),
+            events: Vec::new(),
+        }
+    }
+    pub fn hitbox(&self, name: &str) -> Option<&Hitbox> {
+        self.hitboxes.iter().find(|h| h.name == name)
+    }
+    pub fn hurtbox(&self, name: &str) -> Option<&Hitbox> {
+        self.hurtboxes.iter().find(|h| h.name == name)
+    }
+    pub fn has_event(&self, name: &str) -> bool {
+        self.events.iter().any(|e| e == name)
+    }
 }
 
 #[derive(Debug, Clone, Default)]
 pub struct Animation {
-    frames: Vec<Image>,
+    frames: Vec<Frame>,
     index: usize,
     active: bool,
-    reverse: bool,
-    step: f32,
-    delta: f32,
-    fps: f32,
+    mode: Mode,
+    speed: f32,
+    elapsed: f32,
+    forward: bool,
+    finished: bool,
+    loops: u32,
+    on_finish: Option<Callback>,
+    on_loop: Option<Callback>,
+    effect: Option<Effect>,
+    decorated: Vec<Image>,
+    fired: Vec<String>,
 }
 
 impl Animation {
     pub fn new(frames: Vec<Image>) -> Self {
-        if frames.len() < 1 {
+        let frames = frames
+            .into_iter()
+            .map(|image| Frame::new(image, 1.0 / 60.0))
+            .collect();
+
+        Self::from_frames(frames)
+    }
+    pub fn from_frames(frames: Vec<Frame>) -> Self {
+        if frames.is_empty() {
             panic!("Frame map should contain at least one frame");
         }
         Self {
             frames,
             index: 0,
-            active: false,
-            reverse: false,
-            step: 1.0 / 60.0,
-            delta: 0.0,
-            fps: 0.0,
+            active: true,
+            mode: Mode::Loop,
+            speed: 1.0,
+            elapsed: 0.0,
+            forward: true,
+            finished: false,
+            loops: 0,
+            on_finish: None,
+            on_loop: None,
+            effect: None,
+            decorated: Vec::new(),
+            fired: Vec::new(),
         }
     }
     pub fn index(&self) -> usize {
@@ -87,15 +218,66 @@ impl Animation {
     pub fn active(&self) -> bool {
         self.active
     }
-    pub fn fps(&self) -> f32 {
-        self.fps
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+    pub fn speed(&self) -> f32 {
+        self.speed
+    }
+    pub fn elapsed(&self) -> f32 {
+        self.elapsed
+    }
+    pub fn finished(&self) -> bool {
+        self.finished
+    }
+    pub fn loops(&self) -> u32 {
+        self.loops
     }
     pub fn length(&self) -> usize {
         self.frames.len()
     }
+    // Total play time of one pass through all frames
+    pub fn duration(&self) -> f32 {
+        self.frames.iter().map(|f| f.duration).sum()
+    }
+    // Completed passes plus the fraction of the current pass
+    pub fn progress(&self) -> f32 {
+        if self.finished {
+            return (self.loops + 1) as f32;
+        }
+
+        let duration = self.duration().max(f32::EPSILON);
+        let played = self.frames[..self.index]
+            .iter()
+            .map(|f| f.duration)
+            .sum::<f32>();
+
+        let position = match (self.mode, self.forward) {
+            (Mode::Reverse, _) => self.duration() - played - self.frames[self.index].duration,
+            (Mode::PingPong, false) => {
+                self.duration() * 2.0 - played - self.frames[self.index].duration
+            }
+            _ => played,
+        };
+
+        let pass = match self.mode {
+            Mode::PingPong => duration * 2.0,
+            _ => duration,
+        };
+
+        self.loops as f32 + ((position + self.elapsed) / pass).min(1.0)
+    }
+    pub fn fps(&self) -> f32 {
+        self.frames.len() as f32 / self.duration()
+    }
+    pub fn frames(&self) -> &[Frame] {
+        &self.frames
+    }
     pub fn set_index(&mut self, index: usize) {
         if index < self.frames.len() {
             self.index = index;
+            self.elapsed = 0.0;
+            self.enter();
         } else {
             eprintln!("Index {} must be smaller than {}", index, self.frames.len());
         }
@@ -103,28 +285,102 @@ impl Animation {
     pub fn set_active(&mut self, active: bool) {
         self.active = active;
     }
-    pub fn set_reverse(&mut self, reverse: bool) {
-        self.reverse = reverse;
+    pub fn set_mode(&mut self, mode: Mode) {
+        self.mode = mode;
+    }
+    pub fn set_speed(&mut self, speed: f32) {
+        self.speed = speed.max(0.0);
     }
+    // Set a uniform frame rate for all frames
     pub fn set_fps(&mut self, fps: f32) {
-        self.step = 1.0 / fps;
+        for frame in self.frames.iter_mut() {
+            frame.duration = 1.0 / fps;
+        }
+    }
+    pub fn set_durations(&mut self, durations: &[f32]) {
+        for (frame, duration) in self.frames.iter_mut().zip(durations) {
+            frame.duration = *duration;
+        }
+    }
+    pub fn set_on_finish(&mut self, callback: Option<Callback>) {
+        self.on_finish = callback;
+    }
+    pub fn set_on_loop(&mut self, callback: Option<Callback>) {
+        self.on_loop = callback;
     }
     pub fn set_frames(&mut self, frames: Vec<Image>) {
-        self.index = 0;
-        self.frames = frames;
+        let duration = self.frames.first().map_or(1.0 / 60.0, |f| f.duration);
+        self.frames = frames
+            .into_iter()
+            .map(|image| Frame::new(image, duration))
+            .collect();
+        self.restart();
+        self.set_effect(self.effect);
+    }
+    // Decorate every frame once, so rendering reuses the cached images
+    pub fn set_effect(&mut self, effect: Option<Effect>) {
+        self.effect = effect;
+        self.decorated = match effect {
+            Some(effect) => self.frames.iter().map(|f| effect.apply(&f.image)).collect(),
+            None => Vec::new(),
+        };
+    }
+    pub fn effect(&self) -> Option<Effect> {
+        self.effect
+    }
+    pub fn origin(&self) -> Point {
+        self.effect.map_or(Point::new(0.0, 0.0), |e| e.origin())
+    }
+    // Drawing offset of the current frame, such as trimmed sprite space
+    pub fn offset(&self) -> Point {
+        self.frames[self.index].offset
+    }
+    pub fn pivot(&self) -> Point {
+        self.frames[self.index].pivot
+    }
+    pub fn current(&self) -> &Frame {
+        &self.frames[self.index]
+    }
+    // Hitboxes of the current frame in the space of the pivot placed at position
+    pub fn hitboxes(&self, position: Point) -> Vec<Hitbox> {
+        self.place(&self.frames[self.index].hitboxes, position)
+    }
+    pub fn hurtboxes(&self, position: Point) -> Vec<Hitbox> {
+        self.place(&self.frames[self.index].hurtboxes, position)
+    }
+    pub fn take_events(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.fired)
     }
     pub fn reset(&mut self) {
         self.index = 0;
         self.active = false;
-        self.reverse = false;
-        self.delta = 0.0;
-        self.fps = 0.0;
+        self.elapsed = 0.0;
+        self.forward = true;
+        self.finished = false;
+        self.loops = 0;
+        self.fired.clear();
+    }
+    pub fn restart(&mut self) {
+        self.reset();
+        self.active = true;
+
+        if self.mode == Mode::Reverse {
+            self.index = self.frames.len() - 1;
+        }
+
+        self.enter();
     }
     pub fn frame(&self) -> &Image {
-        &self.frames[self.index]
+        match self.decorated.get(self.index) {
+            Some(frame) => frame,
+            None => &self.frames[self.index].image,
+        }
     }
     pub fn frame_mut(&mut self) -> &mut Image {
-        &mut self.frames[self.index]
+        match self.decorated.get_mut(self.index) {
+            Some(frame) => frame,
+            None => &mut self.frames[self.index].image,
+        }
     }
     pub fn next(&mut self) {
         if self.index == self.frames.len() - 1 {
@@ -140,48 +396,304 @@ impl Animation {
             self.index -= 1;
         }
     }
-    pub fn consecutive(&mut self) {
-        if !self.active || self.frames.len() < 2 {
-            return;
-        };
-        if self.reverse {
-            self.previous();
-        } else {
-            self.next();
-        }
-    }
     pub fn start(&mut self) {
         self.active = true;
-        self.cycle();
     }
     pub fn stop(&mut self) {
         self.active = false;
-        self.delta = 0.0;
-        self.fps = 0.0;
-    }
-    fn cycle(&mut self) {
-        let duration = Duration::from_secs_f32(self.step as f32);
-        let mut now = Instant::now();
-        let mut elapsed;
-        let mut delta;
-
-        while !self.active {
-            elapsed = now.elapsed();
-
-            if duration > elapsed {
-                delta = duration - elapsed;
-            } else {
-                delta = Duration::new(0, 0);
+        self.elapsed = 0.0;
+    }
+    // Advance playback by game time, honoring per frame durations
+    pub fn update(&mut self, delta: f32) {
+        if !self.active || self.finished {
+            return;
+        }
+
+        self.elapsed += delta * self.speed;
+
+        loop {
+            let duration = self.frames[self.index].duration.max(f32::EPSILON);
+
+            if self.elapsed < duration {
+                break;
+            }
+
+            self.elapsed -= duration;
+
+            let index = self.index;
+            let loops = self.loops;
+
+            self.advance();
+
+            if self.index != index || self.loops != loops {
+                self.enter();
+            }
+
+            if self.finished {
+                self.elapsed = 0.0;
+                break;
+            }
+        }
+    }
+    fn advance(&mut self) {
+        let last = self.frames.len() - 1;
+
+        match self.mode {
+            Mode::Loop => {
+                self.next();
+                if self.index == 0 {
+                    self.looped();
+                }
+            }
+            Mode::Reverse => {
+                self.previous();
+                if self.index == last {
+                    self.looped();
+                }
+            }
+            Mode::Once => {
+                if self.index == last {
+                    self.finished = true;
+                    if let Some(callback) = self.on_finish {
+                        callback(self);
+                    }
+                } else {
+                    self.index += 1;
+                }
+            }
+            Mode::PingPong => {
+                if last == 0 {
+                    self.looped();
+                } else if self.forward {
+                    if self.index == last {
+                        self.forward = false;
+                        self.index -= 1;
+                        // Two frames turn around straight onto the first one
+                        if self.index == 0 {
+                            self.looped();
+                        }
+                    } else {
+                        self.index += 1;
+                    }
+                } else if self.index == 0 {
+                    self.forward = true;
+                    self.index += 1;
+                } else {
+                    self.index -= 1;
+                    if self.index == 0 {
+                        self.looped();
+                    }
+                }
+            }
+        }
+    }
+    fn enter(&mut self) {
+        let events = &self.frames[self.index].events;
+        self.fired.extend(events.iter().cloned());
+    }
+    fn place(&self, boxes: &[Hitbox], position: Point) -> Vec<Hitbox> {
+        let pivot = self.pivot();
+
+        boxes
+            .iter()
+            .map(|b| {
+                let x = position.x - pivot.x + b.bounds.x;
+                let y = position.y - pivot.y + b.bounds.y;
+                Hitbox::new(
+                    &b.name,
+                    Rectangle::new(x, y, b.bounds.width, b.bounds.height),
+                )
+            })
+            .collect()
+    }
+    fn looped(&mut self) {
+        self.loops += 1;
+        if let Some(callback) = self.on_loop {
+            callback(self);
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Parameter {
+    Float(f32),
+    Bool(bool),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Condition {
+    True(String),
+    False(String),
+    Greater(String, f32),
+    Less(String, f32),
+    AtLeast(String, f32),
+    AtMost(String, f32),
+}
+
+impl Condition {
+    pub fn test(&self, parameters: &BTreeMap<String, Parameter>) -> bool {
+        let float = |name: &String| match parameters.get(name) {
+            Some(Parameter::Float(value)) => Some(*value),
+            _ => None,
+        };
+        let bool = |name: &String| match parameters.get(name) {
+            Some(Parameter::Bool(value)) => Some(*value),
+            _ => None,
+        };
+
+        match self {
+            Condition::True(name) => bool(name) == Some(true),
+            Condition::False(name) => bool(name) == Some(false),
+            Condition::Greater(name, value) => float(name).is_some_and(|v| v > *value),
+            Condition::Less(name, value) => float(name).is_some_and(|v| v < *value),
+            Condition::AtLeast(name, value) => float(name).is_some_and(|v| v >= *value),
+            Condition::AtMost(name, value) => float(name).is_some_and(|v| v <= *value),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Transition<S> {
+    pub from: Option<S>,
+    pub to: S,
+    pub conditions: Vec<Condition>,
+    pub priority: i32,
+    pub exit: Option<f32>,
+}
+
+impl<S> Transition<S> {
+    pub fn new(from: S, to: S) -> Self {
+        Self {
+            from: Some(from),
+            to,
+            conditions: Vec::new(),
+            priority: 0,
+            exit: None,
+        }
+    }
+    // Transition from any state
+    pub fn any(to: S) -> Self {
+        Self {
+            from: None,
+            to,
+            conditions: Vec::new(),
+            priority: 0,
+            exit: None,
+        }
+    }
+    pub fn when(mut self, condition: Condition) -> Self {
+        self.conditions.push(condition);
+        self
+    }
+    pub fn priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+    // Only leave once the current animation progressed this far, 1.0 is one full pass
+    pub fn exit(mut self, progress: f32) -> Self {
+        self.exit = Some(progress);
+        self
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Event<S> {
+    pub from: S,
+    pub to: S,
+}
+
+// States display as the names of their animations
+#[derive(Debug, Clone, Default)]
+pub struct StateMachine<S> {
+    state: S,
+    parameters: BTreeMap<String, Parameter>,
+    transitions: Vec<Transition<S>>,
+    events: Vec<Event<S>>,
+    on_transition: Option<fn(&Event<S>)>,
+}
+
+impl<S: Copy + PartialEq + Display> StateMachine<S> {
+    pub fn new(state: S) -> Self {
+        Self {
+            state,
+            parameters: BTreeMap::new(),
+            transitions: Vec::new(),
+            events: Vec::new(),
+            on_transition: None,
+        }
+    }
+    pub fn state(&self) -> S {
+        self.state
+    }
+    pub fn add(&mut self, transition: Transition<S>) {
+        self.transitions.push(transition);
+    }
+    pub fn parameter(&self, name: &str) -> Option<Parameter> {
+        self.parameters.get(name).copied()
+    }
+    pub fn set_float(&mut self, name: &str, value: f32) {
+        self.parameters.insert(name.into(), Parameter::Float(value));
+    }
+    pub fn set_bool(&mut self, name: &str, value: bool) {
+        self.parameters.insert(name.into(), Parameter::Bool(value));
+    }
+    pub fn set_on_transition(&mut self, callback: Option<fn(&Event<S>)>) {
+        self.on_transition = callback;
+    }
+    // Transition events since the last call
+    pub fn events(&mut self) -> Vec<Event<S>> {
+        std::mem::take(&mut self.events)
+    }
+    // Follow the highest priority transition whose conditions hold, earlier transitions win ties
+    pub fn update(&mut self, animations: &mut Animations) -> Option<Event<S>> {
+        let progress = animations.get().map_or(f32::MAX, |a| a.progress());
+
+        let mut selected: Option<&Transition<S>> = None;
+
+        for transition in self.transitions.iter() {
+            if transition.from.is_some_and(|from| from != self.state) {
+                continue;
+            }
+            if transition.exit.is_some_and(|exit| progress < exit) {
+                continue;
             }
+            if !transition
+                .conditions
+                .iter()
+                .all(|c| c.test(&self.parameters))
+            {
+                continue;
+            }
+            if selected.is_none_or(|s| transition.priority > s.priority) {
+                selected = Some(transition);
+            }
+        }
+
+        let name = self.state.to_string();
+
+        if animations.name() != name {
+            animations.set(&name);
+        }
 
-            thread::sleep(delta);
+        let to = selected?.to;
 
-            self.delta = (delta + elapsed).as_secs_f32();
-            self.fps = 1.0 / self.delta;
+        if to == self.state {
+            return None;
+        }
 
-            now = Instant::now();
+        let event = Event {
+            from: self.state,
+            to,
+        };
 
-            self.consecutive();
+        self.state = to;
+        animations.set(&to.to_string());
+        self.events.push(event);
+
+        if let Some(callback) = self.on_transition {
+            callback(&event);
         }
+
+        Some(event)
     }
 }
@@ -0,0 +1,855 @@
+use std::io::Read;
+use std::path::Path;
+
+use flate2::read::ZlibDecoder;
+use image::{DynamicImage, RgbaImage};
+use serde::Deserialize;
+
+use super::animation::{Animation, Animations, Frame, Hitbox, Mode};
+use super::asset::{AssetError, Context};
+use super::image::Image;
+use super::point::Point;
+use super::rectangle::Rectangle;
+
+const ASSET: &str = "Aseprite file";
+
+#[derive(Debug, Copy, Clone, Default)]
+pub struct SliceKey {
+    pub frame: usize,
+    pub bounds: Rectangle,
+    pub center: Option<Rectangle>,
+    pub pivot: Option<Point>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Slice {
+    pub name: String,
+    pub keys: Vec<SliceKey>,
+}
+
+impl Slice {
+    // Key in effect for a frame, keys hold until the next key
+    pub fn key(&self, frame: usize) -> Option<&SliceKey> {
+        self.keys.iter().rev().find(|k| k.frame <= frame)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Tag {
+    pub name: String,
+    pub from: usize,
+    pub to: usize,
+    pub mode: Mode,
+}
+
+// Imported sprite: the sheet image, all frames, tags as animations and slices
+#[derive(Debug, Clone, Default)]
+pub struct Sheet {
+    pub image: Image,
+    pub frames: Vec<Frame>,
+    pub tags: Vec<Tag>,
+    pub slices: Vec<Slice>,
+    pub animations: Animations,
+}
+
+impl Sheet {
+    // Slice pivots become frame pivots, slices named hit* and hurt* become hit and hurtboxes
+    pub fn new(image: Image, mut frames: Vec<Frame>, tags: Vec<Tag>, slices: Vec<Slice>) -> Self {
+        for (index, frame) in frames.iter_mut().enumerate() {
+            for slice in slices.iter() {
+                let key = match slice.key(index) {
+                    Some(key) if key.bounds.width > 0.0 && key.bounds.height > 0.0 => key,
+                    _ => continue,
+                };
+
+                if let Some(pivot) = key.pivot {
+                    frame.pivot = Point::new(key.bounds.x + pivot.x, key.bounds.y + pivot.y);
+                }
+
+                let hitbox = Hitbox::new(&slice.name, key.bounds);
+
+                if slice.name.starts_with("hit") {
+                    frame.hitboxes.push(hitbox);
+                } else if slice.name.starts_with("hurt") {
+                    frame.hurtboxes.push(hitbox);
+                }
+            }
+        }
+
+        let mut animations = Animations::new();
+
+        for tag in tags.iter() {
+            let frames = frames[tag.from..=tag.to].to_vec();
+            let mut animation = Animation::from_frames(frames);
+            animation.set_mode(tag.mode);
+            animation.restart();
+            animations.add(&tag.name, animation);
+        }
+
+        if tags.is_empty() && !frames.is_empty() {
+            animations.add("default", Animation::from_frames(frames.clone()));
+        }
+
+        Self {
+            image,
+            frames,
+            tags,
+            slices,
+            animations,
+        }
+    }
+    // JSON sheet export, the sheet image path is resolved relative to the JSON file
+    pub fn from_json_uri(uri: &str) -> Result<Self, AssetError> {
+        let json = std::fs::read_to_string(uri).context(ASSET)?;
+        let data: JsonSheet = serde_json::from_str(&json).context(ASSET)?;
+
+        let directory = Path::new(uri).parent().unwrap_or(Path::new(""));
+        let path = directory.join(&data.meta.image);
+        let path = path
+            .to_str()
+            .ok_or_else(|| AssetError::format(ASSET, "sheet image path is not valid UTF-8"))?;
+
+        let image = Image::from_uri(0.0, 0.0, path).context(ASSET)?;
+
+        Self::from_json_data(data, image)
+    }
+    pub fn from_json(json: &str, image: Image) -> Result<Self, AssetError> {
+        Self::from_json_data(serde_json::from_str(json).context(ASSET)?, image)
+    }
+    pub fn from_ase_uri(uri: &str) -> Result<Self, AssetError> {
+        Self::from_ase_bytes(&std::fs::read(uri).context(ASSET)?)
+    }
+    // Binary .ase and .aseprite files, frames are composited from visible layers
+    pub fn from_ase_bytes(buffer: &[u8]) -> Result<Self, AssetError> {
+        AseFile::parse(buffer)?.into_sheet()
+    }
+    pub fn slice(&self, name: &str) -> Option<&Slice> {
+        self.slices.iter().find(|s| s.name == name)
+    }
+    fn from_json_data(data: JsonSheet, image: Image) -> Result<Self, AssetError> {
+        let entries = data.frames.into_values().context(ASSET)?;
+
+        let mut frames = Vec::with_capacity(entries.len());
+
+        for entry in entries.iter() {
+            let r = entry.frame;
+
+            let (w, h) = if entry.rotated {
+                (r.h, r.w)
+            } else {
+                (r.w, r.h)
+            };
+
+            if r.x + w > image.width() as i32 || r.y + h > image.height() as i32 {
+                return Err(AssetError::format(
+                    ASSET,
+                    format!("frame at {}, {} exceeds the sheet image", r.x, r.y),
+                ));
+            }
+
+            let mut cell =
+                image.cropped(Rectangle::new(r.x as f32, r.y as f32, w as f32, h as f32));
+
+            if entry.rotated {
+                cell = cell.rotated(3);
+            }
+
+            let mut frame = Frame::new(cell, entry.duration as f32 / 1000.0);
+            frame.offset = Point::new(
+                entry.sprite_source_size.x as f32,
+                entry.sprite_source_size.y as f32,
+            );
+
+            frames.push(frame);
+        }
+
+        let mut tags = Vec::with_capacity(data.meta.frame_tags.len());
+
+        for tag in data.meta.frame_tags.iter() {
+            let once = tag.repeat.as_deref().map(|r| r.trim() == "1");
+            tags.push(Tag {
+                name: tag.name.clone(),
+                from: tag.from,
+                to: tag.to,
+                mode: mode(&tag.direction, once.unwrap_or(false)),
+            });
+        }
+
+        let slices = data
+            .meta
+            .slices
+            .iter()
+            .map(|slice| Slice {
+                name: slice.name.clone(),
+                keys: slice
+                    .keys
+                    .iter()
+                    .map(|key| SliceKey {
+                        frame: key.frame,
+                        bounds: key.bounds.rectangle(),
+                        center: key.center.map(|c| c.rectangle()),
+                        pivot: key.pivot.map(|p| Point::new(p.x as f32, p.y as f32)),
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        validate(frames.len(), &tags)?;
+
+        Ok(Self::new(image, frames, tags, slices))
+    }
+}
+
+fn mode(direction: &str, once: bool) -> Mode {
+    match direction {
+        "reverse" => Mode::Reverse,
+        "pingpong" | "pingpong_reverse" => Mode::PingPong,
+        _ if once => Mode::Once,
+        _ => Mode::Loop,
+    }
+}
+
+fn validate(count: usize, tags: &[Tag]) -> Result<(), AssetError> {
+    for tag in tags.iter() {
+        if tag.from > tag.to || tag.to >= count {
+            return Err(AssetError::format(
+                ASSET,
+                format!(
+                    "tag '{}' spans frames {}..={} of {}",
+                    tag.name, tag.from, tag.to, count
+                ),
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[derive(Deserialize, Clone, Copy)]
+struct JsonRectangle {
+    x: i32,
+    y: i32,
+    w: i32,
+    h: i32,
+}
+
+impl JsonRectangle {
+    fn rectangle(&self) -> Rectangle {
+        Rectangle::new(self.x as f32, self.y as f32, self.w as f32, self.h as f32)
+    }
+}
+
+#[derive(Deserialize, Clone, Copy)]
+struct JsonPoint {
+    x: i32,
+    y: i32,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonFrame {
+    frame: JsonRectangle,
+    #[serde(default)]
+    rotated: bool,
+    sprite_source_size: JsonRectangle,
+    duration: u32,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonFrames {
+    Array(Vec<JsonFrame>),
+    Hash(serde_json::Map<String, serde_json::Value>),
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonTag {
+    name: String,
+    from: usize,
+    to: usize,
+    #[serde(default)]
+    direction: String,
+    repeat: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct JsonSliceKey {
+    frame: usize,
+    bounds: JsonRectangle,
+    center: Option<JsonRectangle>,
+    pivot: Option<JsonPoint>,
+}
+
+#[derive(Deserialize)]
+struct JsonSlice {
+    name: String,
+    keys: Vec<JsonSliceKey>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonMeta {
+    #[serde(default)]
+    image: String,
+    #[serde(default)]
+    frame_tags: Vec<JsonTag>,
+    #[serde(default)]
+    slices: Vec<JsonSlice>,
+}
+
+#[derive(Deserialize)]
+struct JsonSheet {
+    frames: JsonFrames,
+    meta: JsonMeta,
+}
+
+impl JsonFrames {
+    fn into_values(self) -> Result<Vec<JsonFrame>, serde_json::Error> {
+        match self {
+            JsonFrames::Array(entries) => Ok(entries),
+            JsonFrames::Hash(map) => map
+                .into_iter()
+                .map(|(_, v)| serde_json::from_value(v))
+                .collect(),
+        }
+    }
+}
+
+// Binary format, see https://github.com/aseprite/aseprite/blob/main/docs/ase-file-specs.md
+const HEADER_MAGIC: u16 = 0xa5e0;
+const FRAME_MAGIC: u16 = 0xf1fa;
+const CHUNK_OLD_PALETTE: u16 = 0x0004;
+const CHUNK_LAYER: u16 = 0x2004;
+const CHUNK_CEL: u16 = 0x2005;
+const CHUNK_TAGS: u16 = 0x2018;
+const CHUNK_PALETTE: u16 = 0x2019;
+const CHUNK_SLICE: u16 = 0x2022;
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, position: 0 }
+    }
+    fn take(&mut self, length: usize) -> Result<&'a [u8], AssetError> {
+        let end = self.position + length;
+
+        if end > self.bytes.len() {
+            return Err(AssetError::format(
+                ASSET,
+                format!("unexpected end of data at byte {}", self.bytes.len()),
+            ));
+        }
+
+        let slice = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(slice)
+    }
+    fn skip(&mut self, length: usize) -> Result<(), AssetError> {
+        self.take(length).map(|_| ())
+    }
+    fn byte(&mut self) -> Result<u8, AssetError> {
+        Ok(self.take(1)?[0])
+    }
+    fn word(&mut self) -> Result<u16, AssetError> {
+        let b = self.take(2)?;
+        Ok(u16::from_le_bytes([b[0], b[1]]))
+    }
+    fn short(&mut self) -> Result<i16, AssetError> {
+        Ok(self.word()? as i16)
+    }
+    fn dword(&mut self) -> Result<u32, AssetError> {
+        let b = self.take(4)?;
+        Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+    fn long(&mut self) -> Result<i32, AssetError> {
+        Ok(self.dword()? as i32)
+    }
+    fn string(&mut self) -> Result<String, AssetError> {
+        let length = self.word()? as usize;
+        Ok(String::from_utf8_lossy(self.take(length)?).into_owned())
+    }
+}
+
+struct AseLayer {
+    visible: bool,
+    group: bool,
+    level: u16,
+    opacity: u8,
+}
+
+#[derive(Clone)]
+struct AseCel {
+    layer: usize,
+    x: i32,
+    y: i32,
+    opacity: u8,
+    z: i16,
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>, // Pixels converted to RGBA
+}
+
+struct AseFile {
+    width: u32,
+    height: u32,
+    depth: u16,
+    transparent: u8,
+    layer_opacity: bool,
+    palette: Vec<[u8; 4]>,
+    layers: Vec<AseLayer>,
+    frames: Vec<(u32, Vec<AseCel>)>,
+    tags: Vec<Tag>,
+    slices: Vec<Slice>,
+}
+
+impl AseFile {
+    fn parse(buffer: &[u8]) -> Result<Self, AssetError> {
+        let mut reader = Reader::new(buffer);
+
+        reader.dword()?; // File size
+        if reader.word()? != HEADER_MAGIC {
+            return Err(AssetError::format(
+                ASSET,
+                "file header magic number is invalid",
+            ));
+        }
+
+        let count = reader.word()? as usize;
+        let width = reader.word()? as u32;
+        let height = reader.word()? as u32;
+        let depth = reader.word()?;
+        let flags = reader.dword()?;
+        reader.skip(2 + 4 + 4)?; // Speed and reserved
+        let transparent = reader.byte()?;
+        reader.skip(128 - 29)?;
+
+        if !matches!(depth, 8 | 16 | 32) {
+            return Err(AssetError::format(
+                ASSET,
+                format!("color depth {} is unsupported", depth),
+            ));
+        }
+
+        let mut file = Self {
+            width,
+            height,
+            depth,
+            transparent,
+            layer_opacity: flags & 1 != 0,
+            palette: Vec::new(),
+            layers: Vec::new(),
+            frames: Vec::with_capacity(count),
+            tags: Vec::new(),
+            slices: Vec::new(),
+        };
+
+        let mut raw_cels = Vec::with_capacity(count);
+
+        for _ in 0..count {
+            let start = reader.position;
+            let size = reader.dword()? as usize;
+
+            if reader.word()? != FRAME_MAGIC {
+                return Err(AssetError::format(
+                    ASSET,
+                    format!("frame at byte {} is invalid", start),
+                ));
+            }
+
+            let old = reader.word()? as u32;
+            let duration = reader.word()? as u32;
+            reader.skip(2)?;
+            let new = reader.dword()?;
+            let chunks = if new == 0 { old } else { new };
+
+            let mut cels = Vec::new();
+
+            for _ in 0..chunks {
+                let chunk_start = reader.position;
+                let chunk_size = reader.dword()? as usize;
+                let kind = reader.word()?;
+
+                if chunk_size < 6 {
+                    return Err(AssetError::format(
+                        ASSET,
+                        format!("chunk at byte {} is invalid", chunk_start),
+                    ));
+                }
+
+                let mut chunk = Reader::new(reader.take(chunk_size - 6)?);
+
+                match kind {
+                    CHUNK_LAYER => file.layers.push(parse_layer(&mut chunk)?),
+                    CHUNK_CEL => cels.push(chunk.bytes),
+                    CHUNK_TAGS => file.tags = parse_tags(&mut chunk)?,
+                    CHUNK_PALETTE => parse_palette(&mut chunk, &mut file.palette)?,
+                    CHUNK_OLD_PALETTE if file.palette.is_empty() => {
+                        parse_old_palette(&mut chunk, &mut file.palette)?
+                    }
+                    CHUNK_SLICE => file.slices.push(parse_slice(&mut chunk)?),
+                    _ => {}
+                }
+            }
+
+            reader.position = start + size;
+            raw_cels.push((duration, cels));
+        }
+
+        // Cels are decoded after all chunks, so palettes and links are known
+        for (index, (duration, chunks)) in raw_cels.into_iter().enumerate() {
+            let mut cels = Vec::with_capacity(chunks.len());
+
+            for bytes in chunks {
+                if let Some(cel) = file.parse_cel(bytes, index)? {
+                    cels.push(cel);
+                }
+            }
+
+            file.frames.push((duration, cels));
+        }
+
+        validate(file.frames.len(), &file.tags)?;
+
+        Ok(file)
+    }
+    fn parse_cel(&self, bytes: &[u8], index: usize) -> Result<Option<AseCel>, AssetError> {
+        let reader = &mut Reader::new(bytes);
+        let layer = reader.word()? as usize;
+        let x = reader.short()? as i32;
+        let y = reader.short()? as i32;
+        let opacity = reader.byte()?;
+        let kind = reader.word()?;
+        let z = reader.short()?;
+        reader.skip(5)?;
+
+        let (width, height, data) = match kind {
+            0 => {
+                let width = reader.word()? as u32;
+                let height = reader.word()? as u32;
+                let length = (width * height) as usize * self.pixel_size();
+                (width, height, reader.take(length)?.to_vec())
+            }
+            2 => {
+                let width = reader.word()? as u32;
+                let height = reader.word()? as u32;
+                let compressed = reader.take(reader.bytes.len() - reader.position)?;
+                let mut data = Vec::new();
+                ZlibDecoder::new(compressed)
+                    .read_to_end(&mut data)
+                    .context(ASSET)?;
+                (width, height, data)
+            }
+            1 => {
+                let link = reader.word()? as usize;
+
+                if link >= index {
+                    return Err(AssetError::format(
+                        ASSET,
+                        format!("cel in frame {} links to frame {}", index, link),
+                    ));
+                }
+
+                let cel = self.frames[link].1.iter().find(|c| c.layer == layer);
+
+                return Ok(cel.map(|cel| AseCel {
+                    opacity,
+                    z,
+                    ..cel.clone()
+                }));
+            }
+            _ => return Ok(None), // Tilemap cels are not supported
+        };
+
+        let expected = (width * height) as usize * self.pixel_size();
+
+        if data.len() < expected {
+            return Err(AssetError::format(
+                ASSET,
+                format!(
+                    "cel on layer {} contains {} of {} bytes",
+                    layer,
+                    data.len(),
+                    expected
+                ),
+            ));
+        }
+
+        Ok(Some(AseCel {
+            layer,
+            x,
+            y,
+            opacity,
+            z,
+            width,
+            height,
+            pixels: self.to_rgba(&data[..expected]),
+        }))
+    }
+    fn pixel_size(&self) -> usize {
+        self.depth as usize / 8
+    }
+    fn to_rgba(&self, data: &[u8]) -> Vec<u8> {
+        match self.depth {
+            32 => data.to_vec(),
+            16 => data
+                .chunks(2)
+                .flat_map(|p| [p[0], p[0], p[0], p[1]])
+                .collect(),
+            _ => data
+                .iter()
+                .flat_map(|i| {
+                    if *i == self.transparent {
+                        [0, 0, 0, 0]
+                    } else {
+                        self.palette
+                            .get(*i as usize)
+                            .copied()
+                            .unwrap_or([0, 0, 0, 0])
+                    }
+                })
+                .collect(),
+        }
+    }
+    fn visible(&self, index: usize) -> bool {
+        let mut level = match self.layers.get(index) {
+            Some(layer) if layer.visible && !layer.group => layer.level,
+            _ => return false,
+        };
+
+        // Hidden parent groups hide their children
+        for layer in self.layers[..index].iter().rev() {
+            if level == 0 {
+                break;
+            }
+            if layer.group && layer.level < level {
+                if !layer.visible {
+                    return false;
+                }
+                level = layer.level;
+            }
+        }
+
+        true
+    }
+    fn compose(&self, cels: &[AseCel]) -> Vec<u8> {
+        let width = self.width as i32;
+        let height = self.height as i32;
+        let mut canvas = vec![0u8; (width * height * 4) as usize];
+
+        let mut order = cels
+            .iter()
+            .filter(|cel| self.visible(cel.layer))
+            .collect::<Vec<&AseCel>>();
+
+        order.sort_by_key(|cel| (cel.layer as i32 + cel.z as i32, cel.z));
+
+        for cel in order {
+            let layer = if self.layer_opacity {
+                self.layers[cel.layer].opacity as u32
+            } else {
+                255
+            };
+            let opacity = cel.opacity as u32 * layer / 255;
+
+            for cy in 0..cel.height as i32 {
+                for cx in 0..cel.width as i32 {
+                    let x = cel.x + cx;
+                    let y = cel.y + cy;
+
+                    if x < 0 || y < 0 || x >= width || y >= height {
+                        continue;
+                    }
+
+                    let source = ((cx + cy * cel.width as i32) * 4) as usize;
+                    let index = ((x + y * width) * 4) as usize;
+
+                    let pixel = &cel.pixels[source..source + 4];
+                    let alpha = pixel[3] as u32 * opacity / 255;
+
+                    if alpha == 0 {
+                        continue;
+                    }
+
+                    let below = canvas[index + 3] as u32 * (255 - alpha) / 255;
+                    let total = alpha + below;
+
+                    for c in 0..3 {
+                        let value = pixel[c] as u32 * alpha + canvas[index + c] as u32 * below;
+                        canvas[index + c] = (value / total) as u8;
+                    }
+
+                    canvas[index + 3] = total as u8;
+                }
+            }
+        }
+
+        canvas
+    }
+    fn into_sheet(self) -> Result<Sheet, AssetError> {
+        let count = self.frames.len() as u32;
+        let mut strip = RgbaImage::new((self.width * count).max(1), self.height.max(1));
+        let mut frames = Vec::with_capacity(self.frames.len());
+
+        for (index, (duration, cels)) in self.frames.iter().enumerate() {
+            let pixels = self.compose(cels);
+            let buffer = RgbaImage::from_raw(self.width, self.height, pixels)
+                .ok_or_else(|| AssetError::format(ASSET, "frame size is invalid"))?;
+
+            image::imageops::replace(&mut strip, &buffer, (index as u32 * self.width) as i64, 0);
+
+            let image = Image::new(0.0, 0.0, DynamicImage::ImageRgba8(buffer));
+            frames.push(Frame::new(image, *duration as f32 / 1000.0));
+        }
+
+        let image = Image::new(0.0, 0.0, DynamicImage::ImageRgba8(strip));
+
+        Ok(Sheet::new(image, frames, self.tags, self.slices))
+    }
+}
+
+fn parse_layer(reader: &mut Reader) -> Result<AseLayer, AssetError> {
+    let flags = reader.word()?;
+    let kind = reader.word()?;
+    let level = reader.word()?;
+    reader.skip(2 + 2 + 2)?; // Default size and blend mode
+    let opacity = reader.byte()?;
+
+    Ok(AseLayer {
+        visible: flags & 1 != 0,
+        group: kind == 1,
+        level,
+        opacity,
+    })
+}
+
+fn parse_tags(reader: &mut Reader) -> Result<Vec<Tag>, AssetError> {
+    let count = reader.word()?;
+    reader.skip(8)?;
+
+    let mut tags = Vec::with_capacity(count as usize);
+
+    for _ in 0..count {
+        let from = reader.word()? as usize;
+        let to = reader.word()? as usize;
+        let direction = reader.byte()?;
+        let repeat = reader.word()?;
+        reader.skip(6 + 3 + 1)?;
+        let name = reader.string()?;
+
+        let direction = match direction {
+            1 => "reverse",
+            2 => "pingpong",
+            3 => "pingpong_reverse",
+            _ => "forward",
+        };
+
+        tags.push(Tag {
+            name,
+            from,
+            to,
+            mode: mode(direction, repeat == 1),
+        });
+    }
+
+    Ok(tags)
+}
+
+fn parse_palette(reader: &mut Reader, palette: &mut Vec<[u8; 4]>) -> Result<(), AssetError> {
+    let size = reader.dword()? as usize;
+    let first = reader.dword()? as usize;
+    let last = reader.dword()? as usize;
+    reader.skip(8)?;
+
+    if palette.len() < size {
+        palette.resize(size, [0, 0, 0, 0]);
+    }
+
+    for index in first..=last {
+        let flags = reader.word()?;
+        let color = reader.take(4)?;
+
+        if index < palette.len() {
+            palette[index] = [color[0], color[1], color[2], color[3]];
+        }
+
+        if flags & 1 != 0 {
+            reader.string()?;
+        }
+    }
+
+    Ok(())
+}
+
+fn parse_old_palette(reader: &mut Reader, palette: &mut Vec<[u8; 4]>) -> Result<(), AssetError> {
+    let packets = reader.word()?;
+    let mut index = 0;
+
+    for _ in 0..packets {
+        index += reader.byte()? as usize;
+        let count = match reader.byte()? {
+            0 => 256,
+            n => n as usize,
+        };
+
+        for _ in 0..count {
+            let color = reader.take(3)?;
+
+            if palette.len() <= index {
+                palette.resize(index + 1, [0, 0, 0, 0]);
+            }
+
+            palette[index] = [color[0], color[1], color[2], 255];
+            index += 1;
+        }
+    }
+
+    Ok(())
+}
+
+fn parse_slice(reader: &mut Reader) -> Result<Slice, AssetError> {
+    let count = reader.dword()?;
+    let flags = reader.dword()?;
+    reader.skip(4)?;
+    let name = reader.string()?;
+
+    let mut keys = Vec::with_capacity(count as usize);
+
+    for _ in 0..count {
+        let frame = reader.dword()? as usize;
+        let x = reader.long()? as f32;
+        let y = reader.long()? as f32;
+        let w = reader.dword()? as f32;
+        let h = reader.dword()? as f32;
+
+        let center = if flags & 1 != 0 {
+            let cx = reader.long()? as f32;
+            let cy = reader.long()? as f32;
+            let cw = reader.dword()? as f32;
+            let ch = reader.dword()? as f32;
+            Some(Rectangle::new(cx, cy, cw, ch))
+        } else {
+            None
+        };
+
+        let pivot = if flags & 2 != 0 {
+            let px = reader.long()? as f32;
+            let py = reader.long()? as f32;
+            Some(Point::new(px, py))
+        } else {
+            None
+        };
+
+        keys.push(SliceKey {
+            frame,
+            bounds: Rectangle::new(x, y, w, h),
+            center,
+            pivot,
+        });
+    }
+
+    Ok(Slice { name, keys })
+}
@@ -0,0 +1,114 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+use image::error::ImageError;
+
+use super::tile::MapError;
+
+// Error of reading or writing an asset, the context names the asset like "Tileset file"
+#[derive(Debug)]
+pub struct AssetError {
+    pub context: String,
+    pub kind: AssetErrorKind,
+}
+
+#[derive(Debug)]
+pub enum AssetErrorKind {
+    Io(io::Error),
+    Json(serde_json::Error),
+    Ron(ron::Error),
+    Xml(roxmltree::Error),
+    Image(ImageError),
+    Map(MapError),
+    Format(String),
+}
+
+impl AssetError {
+    pub fn new(context: &str, kind: AssetErrorKind) -> Self {
+        Self {
+            context: context.into(),
+            kind,
+        }
+    }
+    pub fn format(context: &str, message: impl Into<String>) -> Self {
+        Self::new(context, AssetErrorKind::Format(message.into()))
+    }
+}
+
+impl fmt::Display for AssetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let context = &self.context;
+
+        match &self.kind {
+            AssetErrorKind::Io(e) => write!(f, "{} could not be accessed: {}", context, e),
+            AssetErrorKind::Json(e) => write!(f, "{} contains invalid JSON: {}", context, e),
+            AssetErrorKind::Ron(e) => write!(f, "{} contains invalid RON: {}", context, e),
+            AssetErrorKind::Xml(e) => write!(f, "{} contains invalid XML: {}", context, e),
+            AssetErrorKind::Image(e) => write!(f, "{} image is invalid: {}", context, e),
+            AssetErrorKind::Map(e) => write!(f, "{} is not valid: {}", context, e),
+            AssetErrorKind::Format(e) => write!(f, "{} is malformed: {}", context, e),
+        }
+    }
+}
+
+impl Error for AssetError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match &self.kind {
+            AssetErrorKind::Io(e) => Some(e),
+            AssetErrorKind::Json(e) => Some(e),
+            AssetErrorKind::Ron(e) => Some(e),
+            AssetErrorKind::Xml(e) => Some(e),
+            AssetErrorKind::Image(e) => Some(e),
+            AssetErrorKind::Map(e) => Some(e),
+            AssetErrorKind::Format(_) => None,
+        }
+    }
+}
+
+impl From<io::Error> for AssetErrorKind {
+    fn from(e: io::Error) -> Self {
+        AssetErrorKind::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for AssetErrorKind {
+    fn from(e: serde_json::Error) -> Self {
+        AssetErrorKind::Json(e)
+    }
+}
+
+impl From<ron::Error> for AssetErrorKind {
+    fn from(e: ron::Error) -> Self {
+        AssetErrorKind::Ron(e)
+    }
+}
+
+impl From<roxmltree::Error> for AssetErrorKind {
+    fn from(e: roxmltree::Error) -> Self {
+        AssetErrorKind::Xml(e)
+    }
+}
+
+impl From<ImageError> for AssetErrorKind {
+    fn from(e: ImageError) -> Self {
+        AssetErrorKind::Image(e)
+    }
+}
+
+impl From<MapError> for AssetErrorKind {
+    fn from(e: MapError) -> Self {
+        AssetErrorKind::Map(e)
+    }
+}
+
+// Name the asset a failed result belongs to, so errors convert with ? after context
+pub trait Context<T> {
+    fn context(self, context: &str) -> Result<T, AssetError>;
+}
+
+impl<T, E: Into<AssetErrorKind>> Context<T> for Result<T, E> {
+    fn context(self, context: &str) -> Result<T, AssetError> {
+        self.map_err(|e| AssetError::new(context, e.into()))
+    }
+}
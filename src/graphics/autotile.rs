@@ -0,0 +1,113 @@
+use std::collections::BTreeMap;
+
+pub const NORTH: u8 = 1;
+pub const EAST: u8 = 2;
+pub const SOUTH: u8 = 4;
+pub const WEST: u8 = 8;
+pub const NORTH_EAST: u8 = 16;
+pub const SOUTH_EAST: u8 = 32;
+pub const SOUTH_WEST: u8 = 64;
+pub const NORTH_WEST: u8 = 128;
+
+// Neighbour offsets in column and row with their mask bit, edges before corners
+const NEIGHBOURS: [(i32, i32, u8); 8] = [
+    (0, -1, NORTH),
+    (1, 0, EAST),
+    (0, 1, SOUTH),
+    (-1, 0, WEST),
+    (1, -1, NORTH_EAST),
+    (1, 1, SOUTH_EAST),
+    (-1, 1, SOUTH_WEST),
+    (-1, -1, NORTH_WEST),
+];
+
+// Set of tiles picked by which neighbours belong to the same terrain, 4 bit masks only look
+// at edges while 8 bit masks also count corners enclosed by two edges
+#[derive(Debug, Clone, Default)]
+pub struct Terrain {
+    pub name: String,
+    pub bits: u8,
+    pub rules: BTreeMap<u8, u32>,
+    pub default: Option<u32>,
+}
+
+impl Terrain {
+    pub fn new(name: &str, bits: u8) -> Self {
+        Self {
+            name: name.into(),
+            bits,
+            rules: BTreeMap::new(),
+            default: None,
+        }
+    }
+    pub fn contains(&self, id: u32) -> bool {
+        self.default == Some(id) || self.rules.values().any(|&r| r == id)
+    }
+    // Mask of a cell from a test telling whether a neighbour at an offset is of this terrain
+    pub fn mask(&self, same: impl Fn(i32, i32) -> bool) -> u8 {
+        let mut mask = 0;
+
+        for &(x, y, bit) in NEIGHBOURS.iter().take(4) {
+            if same(x, y) {
+                mask |= bit;
+            }
+        }
+
+        if self.bits == 8 {
+            for &(x, y, bit) in NEIGHBOURS.iter().skip(4) {
+                let enclosed = mask & horizontal(x) != 0 && mask & vertical(y) != 0;
+
+                if enclosed && same(x, y) {
+                    mask |= bit;
+                }
+            }
+        }
+
+        mask
+    }
+    // Tile for a mask, falling back to the edges of the mask and then the default tile
+    pub fn tile(&self, mask: u8) -> Option<u32> {
+        self.rules
+            .get(&mask)
+            .or_else(|| self.rules.get(&(mask & 0x0f)))
+            .copied()
+            .or(self.default)
+    }
+    // Masks are written as directions joined by +, like N+E+SE, or none for isolated tiles
+    pub fn parse_mask(mask: &str) -> Option<u8> {
+        if mask == "none" {
+            return Some(0);
+        }
+
+        mask.split('+').try_fold(0, |mask, direction| {
+            let bit = match direction.trim() {
+                "N" => NORTH,
+                "E" => EAST,
+                "S" => SOUTH,
+                "W" => WEST,
+                "NE" => NORTH_EAST,
+                "SE" => SOUTH_EAST,
+                "SW" => SOUTH_WEST,
+                "NW" => NORTH_WEST,
+                _ => return None,
+            };
+            Some(mask | bit)
+        })
+    }
+}
+
+fn horizontal(x: i32) -> u8 {
+    if x > 0 {
+        EAST
+    } else {
+        WEST
+    }
+}
+
+fn vertical(y: i32) -> u8 {
+    if y > 0 {
+        SOUTH
+    } else {
+        NORTH
+    }
+}
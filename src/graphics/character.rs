@@ -0,0 +1,300 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use super::animation::{Animation, Animations, Frame, Hitbox, Mode};
+use super::asset::{AssetError, Context};
+use super::image::Image;
+use super::point::Point;
+use super::rectangle::Rectangle;
+
+const ASSET: &str = "Character file";
+
+// Loaded character: spritesheet, animations and collision box, frame pivots land on the box origin
+#[derive(Debug, Clone, Default)]
+pub struct Character {
+    pub name: String,
+    pub spritesheet: Image,
+    pub animations: Animations,
+    pub initial: String,
+    pub collision: Rectangle,
+}
+
+impl Character {
+    // Definition file, the spritesheet path is resolved relative to it
+    pub fn from_uri(uri: &str) -> Result<Self, AssetError> {
+        let json = std::fs::read_to_string(uri).context(ASSET)?;
+        let data: Definition = serde_json::from_str(&json).context(ASSET)?;
+
+        let directory = Path::new(uri).parent().unwrap_or(Path::new(""));
+        let path = directory.join(&data.spritesheet);
+        let path = path
+            .to_str()
+            .ok_or_else(|| AssetError::format(ASSET, "spritesheet path is not valid UTF-8"))?;
+
+        let spritesheet = Image::from_uri(0.0, 0.0, path).context(ASSET)?;
+
+        Self::from_definition(data, spritesheet)
+    }
+    // Definition with an already loaded spritesheet, for embedded characters
+    pub fn from_json(json: &str, spritesheet: Image) -> Result<Self, AssetError> {
+        Self::from_definition(serde_json::from_str(json).context(ASSET)?, spritesheet)
+    }
+    fn from_definition(data: Definition, mut spritesheet: Image) -> Result<Self, AssetError> {
+        let width = data.frame.width;
+        let height = data.frame.height;
+
+        if width == 0 || height == 0 {
+            return Err(AssetError::format(ASSET, "frame size should not be zero"));
+        }
+
+        let columns = Image::sheet_columns(&spritesheet, width, data.margin, data.spacing);
+        let rows = Image::sheet_rows(&spritesheet, height, data.margin, data.spacing);
+
+        let pivot = match (data.pivot.as_ref(), data.offset.as_ref()) {
+            (Some(pivot), _) => Position {
+                x: pivot.x,
+                y: pivot.y,
+            },
+            (None, Some(offset)) => Position {
+                x: -offset.x,
+                y: -offset.y,
+            },
+            (None, None) => Position::default(),
+        };
+
+        let mut animations = Animations::new();
+
+        for (name, definition) in data.animations.iter() {
+            if definition.frames.is_empty() {
+                return Err(AssetError::format(
+                    ASSET,
+                    format!("animation {} has no frames", name),
+                ));
+            }
+
+            let indices = definition
+                .frames
+                .iter()
+                .map(|f| f.index())
+                .collect::<Vec<u32>>();
+
+            if let Some(index) = indices.iter().find(|i| **i >= columns * rows) {
+                return Err(AssetError::format(
+                    ASSET,
+                    format!(
+                        "animation {} refers to frame {} outside the spritesheet",
+                        name, index
+                    ),
+                ));
+            }
+
+            if let Some(durations) = definition.durations.as_ref() {
+                if durations.len() != definition.frames.len() {
+                    return Err(AssetError::format(
+                        ASSET,
+                        format!(
+                            "animation {} has {} frames but {} durations",
+                            name,
+                            definition.frames.len(),
+                            durations.len()
+                        ),
+                    ));
+                }
+            }
+
+            let durations = definition
+                .frames
+                .iter()
+                .enumerate()
+                .map(|(i, entry)| match entry {
+                    FrameDefinition::Detailed(FrameDetail {
+                        duration: Some(duration),
+                        ..
+                    }) => *duration,
+                    _ => match definition.durations.as_ref() {
+                        Some(durations) => durations[i],
+                        None => definition.duration.unwrap_or(data.duration),
+                    },
+                })
+                .collect::<Vec<f32>>();
+
+            if durations.iter().any(|&duration| duration <= 0.0) {
+                return Err(AssetError::format(
+                    ASSET,
+                    format!("animation {} has a duration of zero or less", name),
+                ));
+            }
+
+            let images = Image::sheet_to_texture_list(
+                &mut spritesheet,
+                width,
+                height,
+                data.margin,
+                data.spacing,
+                &indices,
+            );
+
+            let frames = images
+                .into_iter()
+                .zip(definition.frames.iter())
+                .enumerate()
+                .map(|(i, (image, entry))| {
+                    let mut frame = Frame::new(image, durations[i]);
+                    let pivot = definition.pivot.as_ref().unwrap_or(&pivot);
+                    frame.pivot = Point::new(pivot.x, pivot.y);
+                    frame.hurtboxes = boxes(&data.hurtboxes);
+
+                    if let FrameDefinition::Detailed(detail) = entry {
+                        if let Some(pivot) = detail.pivot.as_ref() {
+                            frame.pivot = Point::new(pivot.x, pivot.y);
+                        }
+                        if let Some(hurtboxes) = detail.hurtboxes.as_ref() {
+                            frame.hurtboxes = boxes(hurtboxes);
+                        }
+                        frame.hitboxes = boxes(&detail.hitboxes);
+                        frame.events = detail.events.clone();
+                    }
+
+                    frame
+                })
+                .collect();
+
+            let mut animation = Animation::from_frames(frames);
+            animation.set_mode(definition.mode);
+            animation.restart();
+
+            animations.add(name, animation);
+        }
+
+        let initial = match data.initial {
+            Some(initial) if data.animations.contains_key(&initial) => initial,
+            Some(initial) => {
+                return Err(AssetError::format(
+                    ASSET,
+                    format!("initial animation {} does not exist", initial),
+                ))
+            }
+            None => data.animations.keys().next().cloned().unwrap_or_default(),
+        };
+
+        let collision = data.collision;
+
+        Ok(Self {
+            name: data.name,
+            spritesheet,
+            animations,
+            initial,
+            collision: Rectangle::new(0.0, 0.0, collision.width, collision.height),
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct Definition {
+    #[serde(default)]
+    name: String,
+    spritesheet: String,
+    frame: Size,
+    #[serde(default)]
+    margin: u32,
+    #[serde(default)]
+    spacing: u32,
+    #[serde(default = "default_duration")]
+    duration: f32,
+    #[serde(default)]
+    initial: Option<String>,
+    collision: Bounds,
+    #[serde(default)]
+    pivot: Option<Position>,
+    // Sprite offset from the collision box of earlier definitions, the pivot negated
+    #[serde(default)]
+    offset: Option<Position>,
+    #[serde(default)]
+    hurtboxes: Vec<BoxDefinition>,
+    animations: BTreeMap<String, AnimationDefinition>,
+}
+
+#[derive(Deserialize)]
+struct Size {
+    width: u32,
+    height: u32,
+}
+
+#[derive(Deserialize, Default)]
+struct Position {
+    x: f32,
+    y: f32,
+}
+
+#[derive(Deserialize)]
+struct Bounds {
+    width: f32,
+    height: f32,
+}
+
+#[derive(Deserialize)]
+struct BoxDefinition {
+    name: String,
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+}
+
+// Frames are either a bare sheet index or an index with metadata
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum FrameDefinition {
+    Index(u32),
+    Detailed(FrameDetail),
+}
+
+impl FrameDefinition {
+    fn index(&self) -> u32 {
+        match self {
+            FrameDefinition::Index(index) => *index,
+            FrameDefinition::Detailed(detail) => detail.index,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct FrameDetail {
+    index: u32,
+    #[serde(default)]
+    duration: Option<f32>,
+    #[serde(default)]
+    pivot: Option<Position>,
+    #[serde(default)]
+    hitboxes: Vec<BoxDefinition>,
+    #[serde(default)]
+    hurtboxes: Option<Vec<BoxDefinition>>,
+    #[serde(default)]
+    events: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct AnimationDefinition {
+    frames: Vec<FrameDefinition>,
+    #[serde(default)]
+    pivot: Option<Position>,
+    #[serde(default)]
+    duration: Option<f32>,
+    #[serde(default)]
+    durations: Option<Vec<f32>>,
+    #[serde(default)]
+    mode: Mode,
+}
+
+fn boxes(definitions: &[BoxDefinition]) -> Vec<Hitbox> {
+    definitions
+        .iter()
+        .map(|b| Hitbox::new(&b.name, Rectangle::new(b.x, b.y, b.width, b.height)))
+        .collect()
+}
+
+fn default_duration() -> f32 {
+    1.0 / 60.0
+}
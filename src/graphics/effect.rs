@@ -0,0 +1,222 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeMap;
+use std::hash::{Hash, Hasher};
+
+use image::{DynamicImage, RgbaImage};
+
+use super::color::Color;
+use super::image::Image;
+use super::point::Point;
+
+#[derive(Debug, Copy, Clone, Default)]
+pub struct Outline {
+    pub color: Color,
+    pub width: u32,
+}
+
+#[derive(Debug, Copy, Clone, Default)]
+pub struct Shadow {
+    pub color: Color,
+    pub offset: Point,
+}
+
+#[derive(Debug, Copy, Clone, Default)]
+pub struct Effect {
+    pub outline: Option<Outline>,
+    pub shadow: Option<Shadow>,
+}
+
+// Decorated images by source pixels and effect, an effect is applied once per frame image.
+// The cache starts over when it holds more than a limit of images
+#[derive(Debug, Clone, Default)]
+pub struct EffectCache {
+    images: BTreeMap<u64, Image>,
+    limit: usize,
+}
+
+impl Outline {
+    pub fn new(color: Color, width: u32) -> Self {
+        Self { color, width }
+    }
+}
+
+impl Shadow {
+    pub fn new(color: Color, offset: Point) -> Self {
+        Self { color, offset }
+    }
+}
+
+impl Effect {
+    pub fn new(outline: Option<Outline>, shadow: Option<Shadow>) -> Self {
+        Self { outline, shadow }
+    }
+    pub fn outline(color: Color, width: u32) -> Self {
+        Self::new(Some(Outline::new(color, width)), None)
+    }
+    pub fn shadow(color: Color, x: f32, y: f32) -> Self {
+        Self::new(None, Some(Shadow::new(color, Point::new(x, y))))
+    }
+    // Padding around the source image as [left, top, right, bottom]
+    pub fn margin(&self) -> [u32; 4] {
+        let w = self.outline.map_or(0, |o| o.width);
+        let (sx, sy) = self.offset();
+
+        [
+            w + (-sx).max(0) as u32,
+            w + (-sy).max(0) as u32,
+            w + sx.max(0) as u32,
+            w + sy.max(0) as u32,
+        ]
+    }
+    // Position of the decorated image relative to the source image
+    pub fn origin(&self) -> Point {
+        let [left, top, ..] = self.margin();
+        Point::new(-(left as f32), -(top as f32))
+    }
+    pub fn apply(&self, image: &Image) -> Image {
+        let [left, top, right, bottom] = self.margin();
+
+        let sw = image.width() as i32;
+        let sh = image.height() as i32;
+        let width = sw + (left + right) as i32;
+        let height = sh + (top + bottom) as i32;
+
+        let source = image.bytes();
+        let opaque = |x: i32, y: i32| -> bool {
+            x >= 0 && y >= 0 && x < sw && y < sh && source[((x + y * sw) * 4 + 3) as usize] > 0
+        };
+
+        // Silhouette of the sprite including its outline, in output coordinates
+        let mut mask = vec![false; (width * height) as usize];
+        let radius = self.outline.map_or(0, |o| o.width) as i32;
+
+        for y in 0..height {
+            for x in 0..width {
+                let ix = x - left as i32;
+                let iy = y - top as i32;
+
+                mask[(x + y * width) as usize] = (-radius..=radius).any(|dy| {
+                    (-radius..=radius)
+                        .any(|dx| dx * dx + dy * dy <= radius * radius && opaque(ix + dx, iy + dy))
+                });
+            }
+        }
+
+        let mut bytes = vec![0u8; (width * height * 4) as usize];
+
+        let mut fill = |index: usize, color: &Color| {
+            bytes[index] = color.red;
+            bytes[index + 1] = color.green;
+            bytes[index + 2] = color.blue;
+            bytes[index + 3] = color.alpha;
+        };
+
+        if let Some(shadow) = self.shadow {
+            let (sx, sy) = self.offset();
+
+            for y in 0..height {
+                for x in 0..width {
+                    let mx = x - sx;
+                    let my = y - sy;
+
+                    if mx >= 0
+                        && my >= 0
+                        && mx < width
+                        && my < height
+                        && mask[(mx + my * width) as usize]
+                    {
+                        fill(((x + y * width) * 4) as usize, &shadow.color);
+                    }
+                }
+            }
+        }
+
+        if let Some(outline) = self.outline {
+            for (i, _) in mask.iter().enumerate().filter(|(_, m)| **m) {
+                fill(i * 4, &outline.color);
+            }
+        }
+
+        for y in 0..sh {
+            for x in 0..sw {
+                let cursor = ((x + y * sw) * 4) as usize;
+
+                if source[cursor + 3] > 0 {
+                    let index = ((x + left as i32 + (y + top as i32) * width) * 4) as usize;
+                    bytes[index..index + 4].copy_from_slice(&source[cursor..cursor + 4]);
+                }
+            }
+        }
+
+        let buffer = RgbaImage::from_raw(width as u32, height as u32, bytes)
+            .expect("Effect should produce valid RGBA data");
+
+        let origin = self.origin();
+
+        Image::new(
+            image.x() + origin.x,
+            image.y() + origin.y,
+            DynamicImage::ImageRgba8(buffer),
+        )
+    }
+    fn offset(&self) -> (i32, i32) {
+        self.shadow.map_or((0, 0), |s| {
+            (s.offset.x.round() as i32, s.offset.y.round() as i32)
+        })
+    }
+    // Hash of the source pixels and the effect settings, positions are left out
+    fn key(&self, image: &Image) -> u64 {
+        let mut hasher = DefaultHasher::new();
+
+        (image.width(), image.height()).hash(&mut hasher);
+        image.bytes().hash(&mut hasher);
+
+        if let Some(outline) = self.outline {
+            let c = outline.color;
+            (0u8, c.red, c.green, c.blue, c.alpha, outline.width).hash(&mut hasher);
+        }
+        if let Some(shadow) = self.shadow {
+            let c = shadow.color;
+            (1u8, c.red, c.green, c.blue, c.alpha, self.offset()).hash(&mut hasher);
+        }
+
+        hasher.finish()
+    }
+}
+
+impl EffectCache {
+    pub fn new(limit: usize) -> Self {
+        Self {
+            images: BTreeMap::new(),
+            limit,
+        }
+    }
+    // Decorated image placed like the effect places it around the source image
+    pub fn apply(&mut self, effect: &Effect, source: &Image) -> Image {
+        let key = effect.key(source);
+
+        if !self.images.contains_key(&key) && self.images.len() >= self.limit.max(1) {
+            self.images.clear();
+        }
+
+        let origin = effect.origin();
+
+        let mut image = self
+            .images
+            .entry(key)
+            .or_insert_with(|| effect.apply(source))
+            .clone();
+
+        image.set_x_y(source.x() + origin.x, source.y() + origin.y);
+        image
+    }
+    pub fn len(&self) -> usize {
+        self.images.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.images.is_empty()
+    }
+    pub fn clear(&mut self) {
+        self.images.clear();
+    }
+}
@@ -0,0 +1,245 @@
+use serde::Serialize;
+
+use super::tile::{MapError, MapErrorKind};
+use super::tiled::{Object, Properties, Property};
+
+// Thing placed in a level by type name, positions are in tiles
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Entity {
+    pub kind: String,
+    pub x: f32,
+    pub y: f32,
+    #[serde(skip_serializing_if = "Properties::is_empty")]
+    pub properties: Properties,
+}
+
+impl Entity {
+    pub fn new(kind: &str, x: f32, y: f32) -> Self {
+        Self {
+            kind: kind.into(),
+            x,
+            y,
+            properties: Properties::new(),
+        }
+    }
+    // Entity of a Tiled object named by its class or else its name, sizes become properties
+    pub fn from_object(object: &Object, tile_width: f32, tile_height: f32) -> Self {
+        let kind = match object.kind.is_empty() {
+            true => &object.name,
+            false => &object.kind,
+        };
+
+        let mut entity = Self::new(
+            kind,
+            object.bounds.x / tile_width,
+            object.bounds.y / tile_height,
+        );
+
+        entity.properties = object.properties.clone();
+
+        if object.bounds.width > 0.0 && object.bounds.height > 0.0 {
+            let width = (object.bounds.width / tile_width) as f64;
+            let height = (object.bounds.height / tile_height) as f64;
+
+            entity
+                .properties
+                .entry("width".into())
+                .or_insert(Property::Float(width));
+            entity
+                .properties
+                .entry("height".into())
+                .or_insert(Property::Float(height));
+        }
+
+        entity
+    }
+    pub fn f32(&self, name: &str) -> Option<f32> {
+        self.properties.get(name).and_then(|p| p.as_f32())
+    }
+    pub fn bool(&self, name: &str) -> Option<bool> {
+        self.properties.get(name).and_then(|p| p.as_bool())
+    }
+    pub fn str(&self, name: &str) -> Option<&str> {
+        self.properties.get(name).and_then(|p| p.as_str())
+    }
+    // Entities of the entities section of a map, other sections are tile layers
+    pub fn map_to_entities(map: &str) -> Result<Vec<Entity>, MapError> {
+        let mut entities = Vec::new();
+        let mut inside = false;
+
+        for (i, line) in map.split('\n').enumerate() {
+            let trimmed = line.trim();
+
+            if trimmed.starts_with('[') && trimmed.ends_with(']') {
+                inside = is_header(line);
+                continue;
+            }
+
+            if inside {
+                entities.extend(Entity::line_to_entity(line, i + 1)?);
+            }
+        }
+
+        Ok(entities)
+    }
+    // Entity of a type x y key=value line, blank lines hold no entity and quoted values may
+    // contain whitespace
+    pub fn line_to_entity(line: &str, number: usize) -> Result<Option<Entity>, MapError> {
+        let mut fields = fields(line).into_iter();
+
+        let invalid = |(field, column): (&str, usize)| {
+            MapError::new(number, column, MapErrorKind::Entity(field.into()))
+        };
+
+        let kind = match fields.next() {
+            Some((kind, _)) => kind,
+            None => return Ok(None),
+        };
+
+        let end = (line.trim_end(), line.trim_end().chars().count() + 1);
+
+        let x = fields.next().ok_or_else(|| invalid(end))?;
+        let x = x.0.parse::<f32>().map_err(|_| invalid(x))?;
+
+        let y = fields.next().ok_or_else(|| invalid(end))?;
+        let y = y.0.parse::<f32>().map_err(|_| invalid(y))?;
+
+        let mut entity = Entity::new(kind, x, y);
+
+        for field in fields {
+            let property = match field.0.split_once('=') {
+                Some((key, value)) if !key.is_empty() && !key.contains('"') => {
+                    parse_value(value).map(|value| (key, value))
+                }
+                _ => None,
+            };
+
+            let (key, value) = property.ok_or_else(|| invalid(field))?;
+            entity.properties.insert(key.into(), value);
+        }
+
+        Ok(Some(entity))
+    }
+    // Entities section of a map with properties in key order
+    pub fn entities_to_map(entities: &[Entity]) -> String {
+        let mut lines = vec![format!("[{}]", HEADER)];
+
+        for entity in entities.iter() {
+            let mut fields = vec![
+                entity.kind.clone(),
+                entity.x.to_string(),
+                entity.y.to_string(),
+            ];
+
+            for (key, value) in entity.properties.iter() {
+                fields.push(format!("{}={}", key, format_value(value)));
+            }
+
+            lines.push(fields.join(" "));
+        }
+
+        lines.join("\n")
+    }
+}
+
+// Name of the header that starts the entities section
+pub const HEADER: &str = "entities";
+
+pub fn is_header(line: &str) -> bool {
+    line.trim() == format!("[{}]", HEADER)
+}
+
+// Whitespace separated fields with the column they start at, quotes keep whitespace in a field
+fn fields(line: &str) -> Vec<(&str, usize)> {
+    let mut fields = Vec::new();
+    let mut start = None;
+    let (mut quoted, mut escaped) = (false, false);
+
+    let column = |offset: usize| line[..offset].chars().count() + 1;
+
+    for (i, c) in line.char_indices() {
+        if start.is_none() {
+            if c.is_whitespace() {
+                continue;
+            }
+            start = Some(i);
+        }
+
+        if escaped {
+            escaped = false;
+        } else if quoted && c == '\\' {
+            escaped = true;
+        } else if c == '"' {
+            quoted = !quoted;
+        } else if !quoted && c.is_whitespace() {
+            if let Some(start) = start.take() {
+                fields.push((&line[start..i], column(start)));
+            }
+        }
+    }
+
+    if let Some(start) = start {
+        fields.push((&line[start..], column(start)));
+    }
+
+    fields
+}
+
+// Quoted values are strings, others are booleans or numbers where they read as one, and
+// otherwise strings
+fn parse_value(value: &str) -> Option<Property> {
+    if value.starts_with('"') {
+        return unquote(value).map(Property::String);
+    }
+    if value.is_empty() || value.contains('"') {
+        return None;
+    }
+    if let Ok(value) = value.parse::<bool>() {
+        return Some(Property::Bool(value));
+    }
+    if let Ok(value) = value.parse::<i64>() {
+        return Some(Property::Int(value));
+    }
+    if let Ok(value) = value.parse::<f64>() {
+        return Some(Property::Float(value));
+    }
+
+    Some(Property::String(value.into()))
+}
+
+// Text of a quoted value, backslashes escape quotes and backslashes
+fn unquote(value: &str) -> Option<String> {
+    let inner = value.strip_prefix('"')?.strip_suffix('"')?;
+
+    let mut text = String::new();
+    let mut chars = inner.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => text.push(chars.next()?),
+            '"' => return None,
+            c => text.push(c),
+        }
+    }
+
+    Some(text)
+}
+
+// Whole floats keep a decimal so they read back as floats, strings are quoted unless they
+// read back as the same string without
+fn format_value(value: &Property) -> String {
+    match value {
+        Property::Bool(value) => value.to_string(),
+        Property::Int(value) => value.to_string(),
+        Property::Float(value) if value.fract() == 0.0 => format!("{:.1}", value),
+        Property::Float(value) => value.to_string(),
+        Property::String(value) => match parse_value(value) {
+            Some(Property::String(plain))
+                if plain == *value && !value.contains(char::is_whitespace) =>
+            {
+                plain
+            }
+            _ => format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\"")),
+        },
+    }
+}
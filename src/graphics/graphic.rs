@@ -1,5 +1,7 @@
 use super::color::Color;
+use super::effect::{Effect, EffectCache};
 use super::image::Image;
+use super::indexed::IndexedImage;
 use super::point::Point;
 use super::rectangle::Rectangle;
 
@@ -26,6 +28,9 @@ pub enum Graphic {
         point: Point,
         rectangle: Rectangle,
     },
+    Indexed {
+        image: IndexedImage,
+    },
 }
 
 impl Graphic {
@@ -61,6 +66,9 @@ impl Graphic {
             rectangle,
         }
     }
+    pub fn indexed(image: IndexedImage) -> Self {
+        Graphic::Indexed { image }
+    }
 }
 
 impl Graphic {
@@ -82,7 +90,53 @@ impl Graphic {
                 point.x = x;
                 point.y = y;
             }
+            Graphic::Indexed { image } => {
+                image.set_x_y(x, y);
+            }
             _ => {}
         }
     }
+    pub fn bounds(&self) -> Option<Rectangle> {
+        match self {
+            Graphic::Point { point, .. } => Some(Rectangle::new(point.x, point.y, 1.0, 1.0)),
+            Graphic::Rectangle { rectangle, .. } => Some(*rectangle),
+            Graphic::Image { image } => Some(Rectangle::new(
+                image.x(),
+                image.y(),
+                image.width() as f32,
+                image.height() as f32,
+            )),
+            Graphic::Sprite {
+                point, rectangle, ..
+            } => Some(Rectangle::new(
+                point.x,
+                point.y,
+                rectangle.width,
+                rectangle.height,
+            )),
+            Graphic::Indexed { image } => Some(Rectangle::new(
+                image.x(),
+                image.y(),
+                image.width() as f32,
+                image.height() as f32,
+            )),
+            _ => None,
+        }
+    }
+    // Image or sprite frame with an outline or drop shadow, decorated once per frame image
+    pub fn with_effect(&self, effect: &Effect, cache: &mut EffectCache) -> Graphic {
+        match self {
+            Graphic::Image { image } => Graphic::image(cache.apply(effect, image)),
+            Graphic::Sprite {
+                image,
+                point,
+                rectangle,
+            } => {
+                let mut frame = image.cropped(*rectangle);
+                frame.set_x_y(point.x, point.y);
+                Graphic::image(cache.apply(effect, &frame))
+            }
+            _ => self.clone(),
+        }
+    }
 }
@@ -0,0 +1,85 @@
+use std::collections::BTreeMap;
+
+use super::rectangle::Rectangle;
+
+// Spatial index of item bounds by grid cell, items spanning several cells are stored in each
+#[derive(Debug, Clone, Default)]
+pub struct Grid {
+    width: f32,
+    height: f32,
+    cells: BTreeMap<(i32, i32), Vec<usize>>,
+}
+
+impl Grid {
+    pub fn new(width: f32, height: f32) -> Self {
+        Self {
+            width: width.max(1.0),
+            height: height.max(1.0),
+            cells: BTreeMap::new(),
+        }
+    }
+    pub fn from_bounds(width: f32, height: f32, bounds: &[Rectangle]) -> Self {
+        let mut grid = Self::new(width, height);
+
+        for (index, rectangle) in bounds.iter().enumerate() {
+            grid.insert(index, rectangle);
+        }
+
+        grid
+    }
+    pub fn insert(&mut self, index: usize, bounds: &Rectangle) {
+        let (left, top, right, bottom) = self.span(bounds);
+
+        for row in top..=bottom {
+            for column in left..=right {
+                self.cells.entry((column, row)).or_default().push(index);
+            }
+        }
+    }
+    pub fn remove(&mut self, index: usize, bounds: &Rectangle) {
+        let (left, top, right, bottom) = self.span(bounds);
+
+        for row in top..=bottom {
+            for column in left..=right {
+                if let Some(items) = self.cells.get_mut(&(column, row)) {
+                    items.retain(|&i| i != index);
+
+                    if items.is_empty() {
+                        self.cells.remove(&(column, row));
+                    }
+                }
+            }
+        }
+    }
+    pub fn clear(&mut self) {
+        self.cells.clear();
+    }
+    // Items in the cells an area overlaps in ascending order, callers test exact bounds
+    pub fn query(&self, area: &Rectangle) -> Vec<usize> {
+        let (left, top, right, bottom) = self.span(area);
+
+        let mut items = Vec::new();
+
+        for row in top..=bottom {
+            for column in left..=right {
+                if let Some(cell) = self.cells.get(&(column, row)) {
+                    items.extend_from_slice(cell);
+                }
+            }
+        }
+
+        items.sort_unstable();
+        items.dedup();
+
+        items
+    }
+    // Columns and rows covered by a rectangle, right and bottom edges are exclusive
+    fn span(&self, bounds: &Rectangle) -> (i32, i32, i32, i32) {
+        let left = (bounds.x / self.width).floor() as i32;
+        let top = (bounds.y / self.height).floor() as i32;
+        let right = ((bounds.x + bounds.width) / self.width).ceil() as i32 - 1;
+        let bottom = ((bounds.y + bounds.height) / self.height).ceil() as i32 - 1;
+
+        (left, top, right.max(left), bottom.max(top))
+    }
+}
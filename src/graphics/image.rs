@@ -1,7 +1,8 @@
 use std::collections::BTreeMap;
 
 use image::error::ImageError;
-use image::DynamicImage;
+use image::imageops::FilterType;
+use image::{DynamicImage, RgbaImage};
 
 use super::rectangle::Rectangle;
 
@@ -16,6 +17,11 @@ impl Image {
     pub fn new(x: f32, y: f32, image: DynamicImage) -> Self {
         Self { x, y, image }
     }
+    pub fn blank(x: f32, y: f32, width: u32, height: u32) -> Self {
+        let image = DynamicImage::ImageRgba8(RgbaImage::new(width, height));
+
+        Self { x, y, image }
+    }
     pub fn update(&mut self, x: f32, y: f32, image: DynamicImage) {
         self.x = x;
         self.y = y;
@@ -56,6 +62,17 @@ impl Image {
     pub fn bytes(&self) -> &[u8] {
         self.image.as_bytes()
     }
+    pub fn clear(&mut self) {
+        self.bytes_mut().fill(0);
+    }
+    pub fn bytes_mut(&mut self) -> &mut [u8] {
+        if self.image.as_rgba8().is_none() {
+            self.image = DynamicImage::ImageRgba8(self.image.to_rgba8());
+        }
+        self.image
+            .as_mut_rgba8()
+            .expect("Image should contain RGBA data")
+    }
     pub fn crop(&mut self, x: u32, y: u32, width: u32, height: u32) -> Image {
         Self {
             x: self.x,
@@ -75,6 +92,57 @@ impl Image {
             ),
         }
     }
+    pub fn cropped(&self, rectangle: Rectangle) -> Image {
+        Self {
+            x: self.x,
+            y: self.y,
+            image: self.image.crop_imm(
+                rectangle.x as u32,
+                rectangle.y as u32,
+                rectangle.width as u32,
+                rectangle.height as u32,
+            ),
+        }
+    }
+    pub fn scaled(&self, x: f32, y: f32) -> Image {
+        let width = (self.width() as f32 * x).round().max(1.0) as u32;
+        let height = (self.height() as f32 * y).round().max(1.0) as u32;
+
+        Self {
+            x: self.x,
+            y: self.y,
+            image: self.image.resize_exact(width, height, FilterType::Nearest),
+        }
+    }
+    pub fn flipped(&self, horizontal: bool, vertical: bool) -> Image {
+        let image = match (horizontal, vertical) {
+            (true, true) => self.image.rotate180(),
+            (true, false) => self.image.fliph(),
+            (false, true) => self.image.flipv(),
+            (false, false) => self.image.clone(),
+        };
+
+        Self {
+            x: self.x,
+            y: self.y,
+            image,
+        }
+    }
+    // Rotate clockwise by a number of quarter turns
+    pub fn rotated(&self, quarters: u32) -> Image {
+        let image = match quarters % 4 {
+            1 => self.image.rotate90(),
+            2 => self.image.rotate180(),
+            3 => self.image.rotate270(),
+            _ => self.image.clone(),
+        };
+
+        Self {
+            x: self.x,
+            y: self.y,
+            image,
+        }
+    }
     pub fn sprite_to_texture_map(
         sprite: &mut Image,
         width: u32,
@@ -99,4 +167,41 @@ impl Image {
         let map = Image::sprite_to_texture_map(sprite, width, height, indices);
         map.into_values().collect()
     }
+    // Grid sheets with a border margin and spacing between cells, indices run row by row
+    pub fn sheet_to_texture_list(
+        sprite: &mut Image,
+        width: u32,
+        height: u32,
+        margin: u32,
+        spacing: u32,
+        indices: &[u32],
+    ) -> Vec<Image> {
+        let columns = Image::sheet_columns(sprite, width, margin, spacing);
+        Image::grid_to_texture_list(sprite, width, height, margin, spacing, columns, indices)
+    }
+    // Grid sheets with a known number of columns, which may leave unused pixels to the right
+    pub fn grid_to_texture_list(
+        sprite: &mut Image,
+        width: u32,
+        height: u32,
+        margin: u32,
+        spacing: u32,
+        columns: u32,
+        indices: &[u32],
+    ) -> Vec<Image> {
+        let columns = columns.max(1);
+        let mut list = Vec::with_capacity(indices.len());
+        for index in indices {
+            let x = margin + (index % columns) * (width + spacing);
+            let y = margin + (index / columns) * (height + spacing);
+            list.push(sprite.crop(x, y, width, height));
+        }
+        list
+    }
+    pub fn sheet_columns(sprite: &Image, width: u32, margin: u32, spacing: u32) -> u32 {
+        (sprite.width() + spacing).saturating_sub(margin * 2) / (width + spacing)
+    }
+    pub fn sheet_rows(sprite: &Image, height: u32, margin: u32, spacing: u32) -> u32 {
+        (sprite.height() + spacing).saturating_sub(margin * 2) / (height + spacing)
+    }
 }
@@ -0,0 +1,222 @@
+use image::error::{DecodingError, ImageError, ImageFormatHint};
+use image::{DynamicImage, ImageFormat, RgbaImage};
+
+use super::color::Color;
+use super::image::Image;
+
+#[derive(Debug, Copy, Clone, Default)]
+pub struct Cycle {
+    pub start: u8,
+    pub end: u8,
+    pub speed: f32,
+    pub reverse: bool,
+}
+
+impl Cycle {
+    pub fn new(start: u8, end: u8, speed: f32, reverse: bool) -> Self {
+        Self {
+            start,
+            end,
+            speed,
+            reverse,
+        }
+    }
+    pub fn length(&self) -> usize {
+        self.end.saturating_sub(self.start) as usize + 1
+    }
+    pub fn offset(&self, time: f32) -> usize {
+        let length = self.length();
+        let offset = (time * self.speed).floor().max(0.0) as usize % length;
+        if self.reverse {
+            (length - offset) % length
+        } else {
+            offset
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct IndexedImage {
+    x: f32,
+    y: f32,
+    width: u32,
+    height: u32,
+    indices: Vec<u8>,
+    palette: Vec<Color>,
+    colors: Vec<Color>,
+    cycles: Vec<Cycle>,
+}
+
+impl IndexedImage {
+    pub fn new(
+        x: f32,
+        y: f32,
+        width: u32,
+        height: u32,
+        indices: Vec<u8>,
+        palette: Vec<Color>,
+    ) -> Self {
+        if indices.len() != (width * height) as usize {
+            panic!("Index map should contain {} entries", width * height);
+        }
+        Self {
+            x,
+            y,
+            width,
+            height,
+            indices,
+            colors: palette.clone(),
+            palette,
+            cycles: Vec::new(),
+        }
+    }
+    pub fn from_uri(x: f32, y: f32, uri: &str) -> Result<Self, ImageError> {
+        let buffer = std::fs::read(uri).map_err(ImageError::IoError)?;
+
+        Self::from_bytes(x, y, &buffer)
+    }
+    pub fn from_bytes(x: f32, y: f32, buffer: &[u8]) -> Result<Self, ImageError> {
+        let error = |e: Box<dyn std::error::Error + Send + Sync>| {
+            ImageError::Decoding(DecodingError::new(
+                ImageFormatHint::Exact(ImageFormat::Png),
+                e,
+            ))
+        };
+
+        let mut decoder = png::Decoder::new(buffer);
+        decoder.set_transformations(png::Transformations::IDENTITY);
+
+        let mut reader = decoder.read_info().map_err(|e| error(e.into()))?;
+        let mut data = vec![0; reader.output_buffer_size()];
+        let frame = reader.next_frame(&mut data).map_err(|e| error(e.into()))?;
+
+        if frame.color_type != png::ColorType::Indexed {
+            return Err(error("Image should contain indexed colors".into()));
+        }
+
+        let info = reader.info();
+        let rgb = info.palette.as_deref().unwrap_or_default();
+        let trns = info.trns.as_deref().unwrap_or_default();
+
+        let palette = rgb
+            .chunks(3)
+            .enumerate()
+            .map(|(i, c)| Color::new(c[0], c[1], c[2], *trns.get(i).unwrap_or(&255)))
+            .collect::<Vec<Color>>();
+
+        let bits = frame.bit_depth as usize;
+        let mask = ((1u16 << bits) - 1) as u8;
+        let per_byte = 8 / bits;
+
+        let mut indices = Vec::with_capacity((frame.width * frame.height) as usize);
+
+        for line in data.chunks(frame.line_size).take(frame.height as usize) {
+            for x in 0..frame.width as usize {
+                let byte = line[x / per_byte];
+                let shift = 8 - bits * (x % per_byte + 1);
+                indices.push((byte >> shift) & mask);
+            }
+        }
+
+        Ok(Self::new(x, y, frame.width, frame.height, indices, palette))
+    }
+    pub fn x(&self) -> f32 {
+        self.x
+    }
+    pub fn y(&self) -> f32 {
+        self.y
+    }
+    pub fn set_x(&mut self, x: f32) {
+        self.x = x;
+    }
+    pub fn set_y(&mut self, y: f32) {
+        self.y = y;
+    }
+    pub fn set_x_y(&mut self, x: f32, y: f32) {
+        self.x = x;
+        self.y = y;
+    }
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+    pub fn indices(&self) -> &[u8] {
+        &self.indices
+    }
+    pub fn palette(&self) -> &[Color] {
+        &self.palette
+    }
+    pub fn colors(&self) -> &[Color] {
+        &self.colors
+    }
+    pub fn cycles(&self) -> &[Cycle] {
+        &self.cycles
+    }
+    // Cycles that no longer fit the new palette are dropped
+    pub fn set_palette(&mut self, palette: Vec<Color>) {
+        let length = palette.len();
+
+        self.cycles.retain(|cycle| {
+            let fits = (cycle.end as usize) < length;
+            if !fits {
+                eprintln!(
+                    "Cycle {}..={} dropped from palette of {} colors",
+                    cycle.start, cycle.end, length
+                );
+            }
+            fits
+        });
+
+        self.colors = palette.clone();
+        self.palette = palette;
+    }
+    pub fn add_cycle(&mut self, cycle: Cycle) {
+        if cycle.end as usize >= self.palette.len() || cycle.start > cycle.end {
+            eprintln!(
+                "Cycle {}..={} must lie within palette of {} colors",
+                cycle.start,
+                cycle.end,
+                self.palette.len()
+            );
+            return;
+        }
+        self.cycles.push(cycle);
+    }
+    pub fn clear_cycles(&mut self) {
+        self.cycles.clear();
+        self.colors = self.palette.clone();
+    }
+    pub fn color(&self, x: u32, y: u32) -> Color {
+        let index = self.indices[(x + y * self.width) as usize] as usize;
+        self.colors.get(index).copied().unwrap_or_default()
+    }
+    // Rotate cycle ranges of the palette according to game time
+    pub fn update(&mut self, time: f32) {
+        for cycle in self.cycles.iter() {
+            let start = cycle.start as usize;
+            let length = cycle.length();
+            let offset = cycle.offset(time);
+
+            for i in 0..length {
+                self.colors[start + i] = self.palette[start + (i + offset) % length];
+            }
+        }
+    }
+    pub fn to_image(&self) -> Image {
+        let bytes = self
+            .indices
+            .iter()
+            .flat_map(|i| {
+                let c = self.colors.get(*i as usize).copied().unwrap_or_default();
+                [c.red, c.green, c.blue, c.alpha]
+            })
+            .collect::<Vec<u8>>();
+
+        let buffer = RgbaImage::from_raw(self.width, self.height, bytes)
+            .expect("Indexed image should convert to RGBA data");
+
+        Image::new(self.x, self.y, DynamicImage::ImageRgba8(buffer))
+    }
+}
@@ -0,0 +1,475 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use super::entity::{self, Entity};
+use super::grid::Grid;
+use super::point::Point;
+use super::rectangle::Rectangle;
+use super::tile::{scan_line, MapError, MapErrorKind, Tile};
+use super::tileset::Tileset;
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    #[default]
+    Collision,
+    Background,
+    Foreground,
+}
+
+impl Role {
+    pub fn parse(role: &str) -> Option<Role> {
+        match role {
+            "collision" => Some(Role::Collision),
+            "background" => Some(Role::Background),
+            "foreground" => Some(Role::Foreground),
+            _ => None,
+        }
+    }
+    pub fn str(&self) -> &str {
+        match self {
+            Role::Collision => "collision",
+            Role::Background => "background",
+            Role::Foreground => "foreground",
+        }
+    }
+}
+
+// Named tile layer, parallax scales the camera movement, 1 moves along with the level.
+// Tiles are indexed by position, so they only change through methods that reindex
+#[derive(Debug, Clone)]
+pub struct Layer {
+    pub name: String,
+    pub role: Role,
+    pub opacity: f32,
+    pub parallax: Point,
+    tiles: Vec<Tile>,
+    grid: Grid,
+}
+
+impl Layer {
+    pub fn new(name: &str, role: Role, tiles: Vec<Tile>) -> Self {
+        let mut layer = Self {
+            name: name.into(),
+            role,
+            opacity: 1.0,
+            parallax: Point::new(1.0, 1.0),
+            tiles,
+            grid: Grid::default(),
+        };
+        layer.index();
+        layer
+    }
+    pub fn tiles(&self) -> &[Tile] {
+        &self.tiles
+    }
+    // Replace all tiles and reindex, returning the replaced tiles
+    pub fn set_tiles(&mut self, tiles: Vec<Tile>) -> Vec<Tile> {
+        let replaced = std::mem::replace(&mut self.tiles, tiles);
+        self.index();
+        replaced
+    }
+    // Rebuild the spatial index with cells the size of the tiles
+    fn index(&mut self) {
+        let (width, height) = self
+            .tiles
+            .first()
+            .map_or((1.0, 1.0), |t| (t.boundary.width, t.boundary.height));
+
+        let bounds = self
+            .tiles
+            .iter()
+            .map(|t| t.boundary)
+            .collect::<Vec<Rectangle>>();
+
+        self.grid = Grid::from_bounds(width, height, &bounds);
+    }
+    // Tiles in the grid cells overlapping an area
+    pub fn query(&self, area: &Rectangle) -> impl Iterator<Item = &Tile> {
+        self.grid.query(area).into_iter().map(|i| &self.tiles[i])
+    }
+    // Give every terrain tile the tile matching its neighbours
+    pub fn autotile(&mut self, tileset: &Tileset) {
+        let cells = self.cells();
+
+        for (&(column, row), &index) in cells.iter() {
+            if let Some(id) = self.terrain_tile(&cells, tileset, column, row) {
+                self.tiles[index].id = id;
+            }
+        }
+    }
+    // Set or clear the tiles between two corner cells, then autotile them and their neighbours.
+    // The spatial index is updated in place, so painting a cell does not reindex the layer
+    pub fn fill(
+        &mut self,
+        from: (i32, i32),
+        to: (i32, i32),
+        id: Option<u32>,
+        size: (f32, f32),
+        tileset: &Tileset,
+    ) {
+        let (width, height) = size;
+        let (left, right) = (from.0.min(to.0), from.0.max(to.0));
+        let (top, bottom) = (from.1.min(to.1), from.1.max(to.1));
+
+        if self.tiles.is_empty() {
+            self.grid = Grid::new(width, height);
+        }
+
+        let cells = self.cells_between((left, top), (right, bottom), size);
+
+        match id {
+            Some(id) => {
+                for row in top..=bottom {
+                    for column in left..=right {
+                        match cells.get(&(column, row)) {
+                            Some(&index) => self.tiles[index].id = id,
+                            None => {
+                                let position =
+                                    Point::new(column as f32 * width, row as f32 * height);
+                                let boundary =
+                                    Rectangle::new(position.x, position.y, width, height);
+                                self.grid.insert(self.tiles.len(), &boundary);
+                                self.tiles.push(Tile::new(id, position, boundary));
+                            }
+                        }
+                    }
+                }
+            }
+            None => {
+                let mut removed = cells.values().copied().collect::<Vec<usize>>();
+                removed.sort_unstable();
+
+                // Highest first, so a tile swapped into a removed place is never removed later
+                for index in removed.into_iter().rev() {
+                    self.remove(index);
+                }
+            }
+        }
+
+        let cells = self.cells_between((left - 2, top - 2), (right + 2, bottom + 2), size);
+
+        for y in top - 1..=bottom + 1 {
+            for x in left - 1..=right + 1 {
+                let index = match cells.get(&(x, y)) {
+                    Some(&index) => index,
+                    None => continue,
+                };
+                if let Some(id) = self.terrain_tile(&cells, tileset, x, y) {
+                    self.tiles[index].id = id;
+                }
+            }
+        }
+    }
+    // Remove a tile by moving the last tile into its place, keeping the index valid
+    fn remove(&mut self, index: usize) {
+        let last = self.tiles.len() - 1;
+
+        self.grid.remove(index, &self.tiles[index].boundary);
+
+        if index != last {
+            self.grid.remove(last, &self.tiles[last].boundary);
+            self.grid.insert(index, &self.tiles[last].boundary);
+        }
+
+        self.tiles.swap_remove(index);
+    }
+    // Tile index by column and row between two corner cells, found through the spatial index
+    fn cells_between(
+        &self,
+        from: (i32, i32),
+        to: (i32, i32),
+        size: (f32, f32),
+    ) -> BTreeMap<(i32, i32), usize> {
+        let (width, height) = size;
+        let area = Rectangle::new(
+            from.0 as f32 * width,
+            from.1 as f32 * height,
+            (to.0 - from.0 + 1) as f32 * width,
+            (to.1 - from.1 + 1) as f32 * height,
+        );
+
+        self.grid
+            .query(&area)
+            .into_iter()
+            .map(|i| (cell(&self.tiles[i]), i))
+            .filter(|&((column, row), _)| {
+                (from.0..=to.0).contains(&column) && (from.1..=to.1).contains(&row)
+            })
+            .collect()
+    }
+    // Tile index by column and row
+    fn cells(&self) -> BTreeMap<(i32, i32), usize> {
+        self.tiles
+            .iter()
+            .enumerate()
+            .map(|(i, t)| (cell(t), i))
+            .collect()
+    }
+    // Rule tile of a terrain cell for its neighbourhood, cells outside the layer are empty
+    fn terrain_tile(
+        &self,
+        cells: &BTreeMap<(i32, i32), usize>,
+        tileset: &Tileset,
+        column: i32,
+        row: i32,
+    ) -> Option<u32> {
+        let id = self.tiles[*cells.get(&(column, row))?].id;
+        let terrain = tileset.terrain(id)?;
+
+        let mask = terrain.mask(|x, y| {
+            cells
+                .get(&(column + x, row + y))
+                .is_some_and(|&i| terrain.contains(self.tiles[i].id))
+        });
+
+        terrain.tile(mask)
+    }
+    pub fn collides(&self) -> bool {
+        self.role == Role::Collision
+    }
+    // Camera offset of this layer for a level view position
+    pub fn scroll(&self, x: f32, y: f32) -> Point {
+        Point::new(x * self.parallax.x, y * self.parallax.y)
+    }
+    // Sections start with a [name role opacity parallax_x parallax_y] header, trailing fields
+    // are optional, rows before the first header form a collision layer named level and an
+    // [entities] header starts entity lines instead
+    pub fn map_to_layers(map: &str, width: u32, height: u32) -> Result<Vec<Layer>, MapError> {
+        let mut layers = Vec::new();
+
+        for section in sections(map).iter().filter(|s| !s.entities) {
+            layers.push(Layer::from_section(section, width, height)?);
+        }
+
+        Ok(layers)
+    }
+    // Check a map against the level size in tiles and the tile ids that have textures
+    pub fn validate_map(
+        map: &str,
+        columns: u32,
+        rows: u32,
+        known: impl Fn(u32) -> bool,
+    ) -> Result<(), MapError> {
+        for section in sections(map) {
+            if section.entities {
+                for &(number, line) in section.rows.iter() {
+                    let entity = match Entity::line_to_entity(line, number)? {
+                        Some(entity) => entity,
+                        None => continue,
+                    };
+
+                    if entity.x < 0.0 || entity.x >= columns as f32 {
+                        return Err(MapError::new(number, 1, MapErrorKind::Width(columns)));
+                    }
+                    if entity.y < 0.0 || entity.y >= rows as f32 {
+                        return Err(MapError::new(number, 1, MapErrorKind::Height(rows)));
+                    }
+                }
+                continue;
+            }
+
+            if let Some((number, header)) = section.header {
+                parse_header(header, number)?;
+            }
+
+            for (row, &(number, line)) in section.rows.iter().enumerate() {
+                let cells = scan_line(line, number)?;
+
+                if row >= rows as usize && !cells.is_empty() {
+                    return Err(MapError::new(number, 1, MapErrorKind::Height(rows)));
+                }
+
+                for cell in cells.iter() {
+                    if cell.index >= columns as usize {
+                        let kind = MapErrorKind::Width(columns);
+                        return Err(MapError::new(number, cell.column, kind));
+                    }
+                    if !known(cell.id) {
+                        let kind = MapErrorKind::Unknown(cell.id);
+                        return Err(MapError::new(number, cell.column, kind));
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+    // Map of layers where every layer spans the level size in tiles, the first layer omits
+    // its header when it is the default one
+    pub fn layers_to_map(layers: &[Layer], columns: u32, rows: u32) -> Result<String, MapError> {
+        let mut lines = Vec::new();
+
+        for (i, layer) in layers.iter().enumerate() {
+            let header = format_header(&Header {
+                name: layer.name.clone(),
+                role: layer.role,
+                opacity: layer.opacity,
+                parallax: layer.parallax,
+            });
+
+            if i > 0 || header != format_header(&Header::default()) {
+                lines.push(header);
+            }
+
+            // Lines written so far, rows of a layer are a single entry
+            let offset = lines.iter().map(|l| l.split('\n').count()).sum::<usize>();
+            let map = Tile::tiles_to_map(&layer.tiles, columns, rows).map_err(|mut e| {
+                e.line += offset;
+                e
+            })?;
+
+            lines.push(map);
+        }
+
+        Ok(lines.join("\n"))
+    }
+    fn from_section(section: &Section, width: u32, height: u32) -> Result<Layer, MapError> {
+        let mut tiles = Vec::new();
+
+        for (row, &(number, line)) in section.rows.iter().enumerate() {
+            tiles.extend(Tile::line_to_tiles(line, number, row, width, height)?);
+        }
+
+        let header = match section.header {
+            Some((number, header)) => parse_header(header, number)?,
+            None => Header::default(),
+        };
+
+        let mut layer = Layer::new(&header.name, header.role, tiles);
+
+        layer.opacity = header.opacity;
+        layer.parallax = header.parallax;
+
+        Ok(layer)
+    }
+}
+
+// Column and row of a tile
+fn cell(tile: &Tile) -> (i32, i32) {
+    let column = (tile.position.x / tile.boundary.width).round() as i32;
+    let row = (tile.position.y / tile.boundary.height).round() as i32;
+    (column, row)
+}
+
+// Lines of a layer or of the entities with their line numbers, counted from 1
+struct Section<'a> {
+    header: Option<(usize, &'a str)>,
+    rows: Vec<(usize, &'a str)>,
+    entities: bool,
+}
+
+fn sections(map: &str) -> Vec<Section<'_>> {
+    let mut sections = Vec::new();
+    let mut section = Section {
+        header: None,
+        rows: Vec::new(),
+        entities: false,
+    };
+
+    for (i, line) in map.split('\n').enumerate() {
+        let trimmed = line.trim();
+
+        if trimmed.starts_with('[') && trimmed.ends_with(']') {
+            if section.header.is_some() || !section.rows.is_empty() {
+                sections.push(section);
+            }
+            section = Section {
+                header: Some((i + 1, line)),
+                rows: Vec::new(),
+                entities: entity::is_header(line),
+            };
+        } else {
+            section.rows.push((i + 1, line));
+        }
+    }
+
+    if section.header.is_some() || !section.rows.is_empty() {
+        sections.push(section);
+    }
+
+    sections
+}
+
+struct Header {
+    name: String,
+    role: Role,
+    opacity: f32,
+    parallax: Point,
+}
+
+impl Default for Header {
+    fn default() -> Self {
+        Self {
+            name: "level".into(),
+            role: Role::Collision,
+            opacity: 1.0,
+            parallax: Point::new(1.0, 1.0),
+        }
+    }
+}
+
+fn parse_header(line: &str, number: usize) -> Result<Header, MapError> {
+    let start = line.find('[').unwrap_or(0) + 1;
+    let end = line.rfind(']').unwrap_or(line.len());
+
+    // Fields with the column they start at
+    let mut fields = line[start..end]
+        .split_whitespace()
+        .map(|field| {
+            let offset = field.as_ptr() as usize - line.as_ptr() as usize;
+            (field, line[..offset].chars().count() + 1)
+        })
+        .collect::<Vec<(&str, usize)>>()
+        .into_iter();
+
+    let invalid = |(field, column): (&str, usize)| {
+        MapError::new(number, column, MapErrorKind::Header(field.into()))
+    };
+
+    let mut header = Header::default();
+
+    if let Some((name, _)) = fields.next() {
+        header.name = name.into();
+    }
+    if let Some(field) = fields.next() {
+        header.role = Role::parse(field.0).ok_or_else(|| invalid(field))?;
+    }
+    if let Some(field) = fields.next() {
+        let opacity = field.0.parse::<f32>().map_err(|_| invalid(field))?;
+        header.opacity = opacity.clamp(0.0, 1.0);
+    }
+    if let Some(field) = fields.next() {
+        header.parallax.x = field.0.parse().map_err(|_| invalid(field))?;
+        header.parallax.y = header.parallax.x;
+    }
+    if let Some(field) = fields.next() {
+        header.parallax.y = field.0.parse().map_err(|_| invalid(field))?;
+    }
+    if let Some(field) = fields.next() {
+        return Err(invalid(field));
+    }
+
+    Ok(header)
+}
+
+// Header with trailing fields left out when they hold their defaults
+fn format_header(header: &Header) -> String {
+    let mut fields = vec![header.name.clone(), header.role.str().into()];
+
+    let parallax = header.parallax;
+    let scaled = parallax.x != 1.0 || parallax.y != 1.0;
+
+    if header.opacity != 1.0 || scaled {
+        fields.push(header.opacity.to_string());
+    }
+    if scaled {
+        fields.push(parallax.x.to_string());
+    }
+    if parallax.y != parallax.x {
+        fields.push(parallax.y.to_string());
+    }
+
+    format!("[{}]", fields.join(" "))
+}
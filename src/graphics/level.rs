@@ -0,0 +1,126 @@
+use std::fs;
+use std::path::Path;
+
+use ron::ser::PrettyConfig;
+use serde::Serialize;
+
+use super::asset::{AssetError, Context};
+use super::entity::Entity;
+use super::layer::{Layer, Role};
+use super::tile::Flip;
+use super::tiled::{Object, Properties};
+
+const ASSET: &str = "Level export";
+
+// Whole level for external tools, tiles are placed by column and row counted from 0
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Level {
+    pub columns: u32,
+    pub rows: u32,
+    pub tile_width: u32,
+    pub tile_height: u32,
+    pub metadata: Properties,
+    pub layers: Vec<LevelLayer>,
+    pub objects: Vec<LevelObject>,
+    pub entities: Vec<Entity>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LevelLayer {
+    pub name: String,
+    pub role: Role,
+    pub opacity: f32,
+    pub parallax: (f32, f32),
+    pub tiles: Vec<LevelTile>,
+}
+
+#[derive(Debug, Copy, Clone, Serialize)]
+pub struct LevelTile {
+    pub column: i32,
+    pub row: i32,
+    pub id: u32,
+    #[serde(skip_serializing_if = "Flip::is_none")]
+    pub flip: Flip,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LevelObject {
+    pub id: u32,
+    pub name: String,
+    pub kind: String,
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    #[serde(skip_serializing_if = "Properties::is_empty")]
+    pub properties: Properties,
+}
+
+impl Level {
+    pub fn new(columns: u32, rows: u32, tile_width: u32, tile_height: u32) -> Self {
+        Self {
+            columns,
+            rows,
+            tile_width,
+            tile_height,
+            ..Self::default()
+        }
+    }
+    pub fn add_layer(&mut self, layer: &Layer) {
+        let tiles = layer
+            .tiles()
+            .iter()
+            .map(|t| LevelTile {
+                column: (t.position.x / t.boundary.width).round() as i32,
+                row: (t.position.y / t.boundary.height).round() as i32,
+                id: t.id,
+                flip: t.flip,
+            })
+            .collect();
+
+        self.layers.push(LevelLayer {
+            name: layer.name.clone(),
+            role: layer.role,
+            opacity: layer.opacity,
+            parallax: (layer.parallax.x, layer.parallax.y),
+            tiles,
+        });
+    }
+    pub fn add_object(&mut self, object: &Object) {
+        self.objects.push(LevelObject {
+            id: object.id,
+            name: object.name.clone(),
+            kind: object.kind.clone(),
+            x: object.bounds.x,
+            y: object.bounds.y,
+            width: object.bounds.width,
+            height: object.bounds.height,
+            properties: object.properties.clone(),
+        });
+    }
+    pub fn to_json(&self) -> Result<String, AssetError> {
+        serde_json::to_string_pretty(self).context(ASSET)
+    }
+    pub fn to_ron(&self) -> Result<String, AssetError> {
+        ron::ser::to_string_pretty(self, PrettyConfig::default()).context(ASSET)
+    }
+    // Write the level in the format named by the file extension, json or ron
+    pub fn save(&self, uri: &str) -> Result<(), AssetError> {
+        let extension = Path::new(uri).extension().and_then(|e| e.to_str());
+
+        let text = match extension {
+            Some("json") => self.to_json()?,
+            Some("ron") => self.to_ron()?,
+            _ => {
+                return Err(AssetError::format(
+                    ASSET,
+                    format!("{} is not a json or ron file", uri),
+                ))
+            }
+        };
+
+        fs::write(uri, text).context(ASSET)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,195 @@
+use image::error::{ImageError, ParameterError, ParameterErrorKind};
+use image::DynamicImage;
+
+#[derive(Debug, Clone, Default)]
+pub struct Lut {
+    size: u32,
+    table: Vec<[u8; 3]>,
+}
+
+impl Lut {
+    pub fn new(size: u32, table: Vec<[u8; 3]>) -> Self {
+        if size < 2 || table.len() != (size * size * size) as usize {
+            panic!(
+                "Lookup table of size {} should contain {} entries",
+                size,
+                size.pow(3)
+            );
+        }
+        Self { size, table }
+    }
+    pub fn identity(size: u32) -> Self {
+        let scale = 255.0 / (size - 1) as f32;
+        let value = |c: u32| (c as f32 * scale).round() as u8;
+
+        let mut table = Vec::with_capacity((size * size * size) as usize);
+
+        for b in 0..size {
+            for g in 0..size {
+                for r in 0..size {
+                    table.push([value(r), value(g), value(b)]);
+                }
+            }
+        }
+
+        Self::new(size, table)
+    }
+    pub fn from_uri(uri: &str) -> Result<Self, ImageError> {
+        Self::from_image(image::open(uri)?)
+    }
+    pub fn from_bytes(buffer: &[u8]) -> Result<Self, ImageError> {
+        Self::from_image(image::load_from_memory(buffer)?)
+    }
+    // Strip layout: blue slices side by side, red along x and green along y
+    pub fn from_image(image: DynamicImage) -> Result<Self, ImageError> {
+        let image = image.to_rgba8();
+        let size = image.height();
+
+        if size < 2 || image.width() != size * size {
+            return Err(ImageError::Parameter(ParameterError::from_kind(
+                ParameterErrorKind::DimensionMismatch,
+            )));
+        }
+
+        let mut table = Vec::with_capacity((size * size * size) as usize);
+
+        for b in 0..size {
+            for g in 0..size {
+                for r in 0..size {
+                    let pixel = image.get_pixel(r + b * size, g);
+                    table.push([pixel[0], pixel[1], pixel[2]]);
+                }
+            }
+        }
+
+        Ok(Self::new(size, table))
+    }
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+    // Trilinear lookup of a color
+    pub fn sample(&self, red: u8, green: u8, blue: u8) -> [u8; 3] {
+        let max = self.size as usize - 1;
+        let scale = max as f32 / 255.0;
+
+        let r = red as f32 * scale;
+        let g = green as f32 * scale;
+        let b = blue as f32 * scale;
+
+        let (r0, g0, b0) = (r as usize, g as usize, b as usize);
+        let (r1, g1, b1) = ((r0 + 1).min(max), (g0 + 1).min(max), (b0 + 1).min(max));
+        let (fr, fg, fb) = (r - r0 as f32, g - g0 as f32, b - b0 as f32);
+
+        let mut color = [0u8; 3];
+
+        for (c, value) in color.iter_mut().enumerate() {
+            let at = |r: usize, g: usize, b: usize| self.at(r, g, b)[c] as f32;
+
+            let c00 = at(r0, g0, b0) * (1.0 - fr) + at(r1, g0, b0) * fr;
+            let c10 = at(r0, g1, b0) * (1.0 - fr) + at(r1, g1, b0) * fr;
+            let c01 = at(r0, g0, b1) * (1.0 - fr) + at(r1, g0, b1) * fr;
+            let c11 = at(r0, g1, b1) * (1.0 - fr) + at(r1, g1, b1) * fr;
+
+            let c0 = c00 * (1.0 - fg) + c10 * fg;
+            let c1 = c01 * (1.0 - fg) + c11 * fg;
+
+            *value = (c0 * (1.0 - fb) + c1 * fb).round() as u8;
+        }
+
+        color
+    }
+    fn at(&self, r: usize, g: usize, b: usize) -> [u8; 3] {
+        let size = self.size as usize;
+        self.table[r + g * size + b * size * size]
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Grading {
+    name: String,
+    previous: String,
+    current: Option<Lut>,
+    target: Option<Lut>,
+    progress: f32,
+    duration: f32,
+}
+
+impl Grading {
+    pub fn new() -> Self {
+        Self {
+            name: "".into(),
+            previous: "".into(),
+            current: None,
+            target: None,
+            progress: 0.0,
+            duration: 0.0,
+        }
+    }
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    pub fn current(&self) -> Option<&Lut> {
+        self.current.as_ref()
+    }
+    pub fn target(&self) -> Option<&Lut> {
+        self.target.as_ref()
+    }
+    pub fn progress(&self) -> f32 {
+        self.progress
+    }
+    pub fn fading(&self) -> bool {
+        self.name != self.previous
+    }
+    pub fn active(&self) -> bool {
+        self.current.is_some() || self.target.is_some()
+    }
+    pub fn set(&mut self, name: &str, lut: Option<Lut>) {
+        self.name = name.into();
+        self.previous = name.into();
+        self.current = lut;
+        self.target = None;
+        self.progress = 0.0;
+    }
+    // Cross-fade towards another table, an empty name fades out grading
+    pub fn fade(&mut self, name: &str, lut: Option<Lut>, duration: f32) {
+        if self.name == name {
+            return;
+        }
+
+        if self.fading() {
+            if self.previous == name {
+                std::mem::swap(&mut self.current, &mut self.target);
+                std::mem::swap(&mut self.name, &mut self.previous);
+                self.progress = 1.0 - self.progress;
+                return;
+            }
+            self.finish();
+        }
+
+        if duration <= 0.0 {
+            self.set(name, lut);
+            return;
+        }
+
+        self.previous = std::mem::replace(&mut self.name, name.into());
+        self.target = lut;
+        self.progress = 0.0;
+        self.duration = duration;
+    }
+    pub fn update(&mut self, delta: f32) {
+        if !self.fading() {
+            return;
+        }
+
+        self.progress += delta / self.duration;
+
+        if self.progress >= 1.0 {
+            self.finish();
+        }
+    }
+    fn finish(&mut self) {
+        self.current = self.target.take();
+        self.previous = self.name.clone();
+        self.progress = 0.0;
+    }
+}
@@ -0,0 +1,226 @@
+use std::collections::BTreeMap;
+
+use image::{DynamicImage, RgbaImage};
+
+use super::color::Color;
+use super::image::Image;
+use super::point::Point;
+use super::rectangle::Rectangle;
+use super::tile::Tile;
+
+#[derive(Debug, Clone, Default)]
+pub struct Minimap {
+    columns: u32,
+    rows: u32,
+    scale: u32,
+    tile_width: f32,
+    tile_height: f32,
+    image: Image,
+    fog: Image,
+    explored: Vec<bool>,
+    fogged: bool,
+    background: Color,
+    view: Color,
+    subject: Color,
+}
+
+impl Minimap {
+    pub fn new(columns: u32, rows: u32, scale: u32, tile_width: u32, tile_height: u32) -> Self {
+        let width = columns * scale;
+        let height = rows * scale;
+
+        let blank = |color: Color| {
+            let pixel = image::Rgba([color.red, color.green, color.blue, color.alpha]);
+            let buffer = RgbaImage::from_pixel(width, height, pixel);
+            Image::new(0.0, 0.0, DynamicImage::ImageRgba8(buffer))
+        };
+
+        let background = Color::new(0, 0, 0, 128);
+        let fog = Color::new(32, 32, 40, 224);
+        let view = Color::new(255, 255, 255, 255);
+        let subject = Color::new(255, 64, 64, 255);
+
+        Self {
+            columns,
+            rows,
+            scale,
+            tile_width: tile_width as f32,
+            tile_height: tile_height as f32,
+            image: blank(background),
+            fog: blank(fog),
+            explored: vec![false; (columns * rows) as usize],
+            fogged: true,
+            background,
+            view,
+            subject,
+        }
+    }
+    pub fn x(&self) -> f32 {
+        self.image.x()
+    }
+    pub fn y(&self) -> f32 {
+        self.image.y()
+    }
+    pub fn set_x_y(&mut self, x: f32, y: f32) {
+        self.image.set_x_y(x, y);
+        self.fog.set_x_y(x, y);
+    }
+    pub fn width(&self) -> u32 {
+        self.image.width()
+    }
+    pub fn height(&self) -> u32 {
+        self.image.height()
+    }
+    pub fn image(&self) -> &Image {
+        &self.image
+    }
+    pub fn fog(&self) -> Option<&Image> {
+        self.fogged.then_some(&self.fog)
+    }
+    pub fn set_fog(&mut self, fogged: bool) {
+        self.fogged = fogged;
+    }
+    pub fn view_color(&self) -> Color {
+        self.view
+    }
+    pub fn subject_color(&self) -> Color {
+        self.subject
+    }
+    // The background is painted by rasterize, so callers have to rasterize again to see it
+    pub fn set_colors(&mut self, background: Color, view: Color, subject: Color) {
+        self.background = background;
+        self.view = view;
+        self.subject = subject;
+    }
+    pub fn explored(&self, column: u32, row: u32) -> bool {
+        column < self.columns
+            && row < self.rows
+            && self.explored[(column + row * self.columns) as usize]
+    }
+    // Rasterize tiles at scale pixels per tile, colored by their average texture color
+    pub fn rasterize(&mut self, tiles: &[Tile], textures: &BTreeMap<u32, Image>) {
+        let colors = textures
+            .iter()
+            .map(|(id, image)| (*id, average(image)))
+            .collect::<BTreeMap<u32, Color>>();
+
+        let width = self.width() as usize;
+        let background = self.background;
+        let bytes = self.image.bytes_mut();
+
+        for pixel in bytes.chunks_exact_mut(4) {
+            pixel.copy_from_slice(&[
+                background.red,
+                background.green,
+                background.blue,
+                background.alpha,
+            ]);
+        }
+
+        for tile in tiles.iter() {
+            let column = (tile.position.x / self.tile_width).floor() as i32;
+            let row = (tile.position.y / self.tile_height).floor() as i32;
+
+            if column < 0 || row < 0 || column as u32 >= self.columns || row as u32 >= self.rows {
+                continue;
+            }
+
+            let color = colors
+                .get(&tile.id)
+                .copied()
+                .unwrap_or(Color::new(255, 255, 255, 255));
+
+            for y in 0..self.scale as usize {
+                for x in 0..self.scale as usize {
+                    let px = column as usize * self.scale as usize + x;
+                    let py = row as usize * self.scale as usize + y;
+                    let index = (px + py * width) * 4;
+
+                    bytes[index..index + 4].copy_from_slice(&[
+                        color.red,
+                        color.green,
+                        color.blue,
+                        255,
+                    ]);
+                }
+            }
+        }
+    }
+    // Mark tiles overlapping the rectangle as explored and clear their fog
+    pub fn explore(&mut self, view: &Rectangle) {
+        let x0 = (view.x / self.tile_width).floor().max(0.0) as u32;
+        let y0 = (view.y / self.tile_height).floor().max(0.0) as u32;
+        let x1 =
+            (((view.x + view.width) / self.tile_width).ceil().max(0.0) as u32).min(self.columns);
+        let y1 =
+            (((view.y + view.height) / self.tile_height).ceil().max(0.0) as u32).min(self.rows);
+
+        let width = self.width() as usize;
+        let scale = self.scale as usize;
+
+        for row in y0..y1 {
+            for column in x0..x1 {
+                let cell = (column + row * self.columns) as usize;
+
+                if self.explored[cell] {
+                    continue;
+                }
+
+                self.explored[cell] = true;
+
+                let bytes = self.fog.bytes_mut();
+
+                for y in 0..scale {
+                    for x in 0..scale {
+                        let px = column as usize * scale + x;
+                        let py = row as usize * scale + y;
+                        bytes[(px + py * width) * 4 + 3] = 0;
+                    }
+                }
+            }
+        }
+    }
+    // Convert level coordinates to screen coordinates on the minimap
+    pub fn project(&self, point: &Point) -> Point {
+        let scale = self.scale as f32;
+
+        Point::new(
+            self.x() + point.x / self.tile_width * scale,
+            self.y() + point.y / self.tile_height * scale,
+        )
+    }
+    pub fn project_rectangle(&self, rectangle: &Rectangle) -> Rectangle {
+        let scale = self.scale as f32;
+        let origin = self.project(&Point::new(rectangle.x, rectangle.y));
+
+        Rectangle::new(
+            origin.x,
+            origin.y,
+            rectangle.width / self.tile_width * scale,
+            rectangle.height / self.tile_height * scale,
+        )
+    }
+}
+
+fn average(image: &Image) -> Color {
+    let mut sum = [0u32; 3];
+    let mut count = 0;
+
+    for pixel in image.bytes().chunks_exact(4).filter(|p| p[3] > 0) {
+        sum[0] += pixel[0] as u32;
+        sum[1] += pixel[1] as u32;
+        sum[2] += pixel[2] as u32;
+        count += 1;
+    }
+
+    if count == 0 {
+        return Color::new(0, 0, 0, 0);
+    }
+
+    Color::new(
+        (sum[0] / count) as u8,
+        (sum[1] / count) as u8,
+        (sum[2] / count) as u8,
+        255,
+    )
+}
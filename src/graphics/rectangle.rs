@@ -1,3 +1,5 @@
+use super::point::Point;
+
 #[derive(Debug, Copy, Clone, Default)]
 pub struct Rectangle {
     pub x: f32,
@@ -21,4 +23,25 @@ impl Rectangle {
     pub fn from_tuple(r: &(i32, i32, u32, u32)) -> Rectangle {
         Rectangle::new(r.0 as f32, r.1 as f32, r.2 as f32, r.3 as f32)
     }
+    pub fn intersects(&self, other: &Rectangle) -> bool {
+        self.x < other.x + other.width
+            && other.x < self.x + self.width
+            && self.y < other.y + other.height
+            && other.y < self.y + self.height
+    }
+    // Smallest rectangle covering both
+    pub fn union(&self, other: &Rectangle) -> Rectangle {
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        let right = (self.x + self.width).max(other.x + other.width);
+        let bottom = (self.y + self.height).max(other.y + other.height);
+
+        Rectangle::new(x, y, right - x, bottom - y)
+    }
+    pub fn contains(&self, point: &Point) -> bool {
+        point.x >= self.x
+            && point.y >= self.y
+            && point.x < self.x + self.width
+            && point.y < self.y + self.height
+    }
 }
@@ -0,0 +1,277 @@
+use std::borrow::Cow;
+use std::f32::consts::FRAC_PI_2;
+
+use super::graphic::Graphic;
+use super::image::Image;
+use super::point::Point;
+use super::rectangle::Rectangle;
+
+#[derive(Debug, Copy, Clone)]
+pub struct Transform {
+    pub translation: Point,
+    pub scale: Point,
+    pub rotation: f32,
+    pub flip_x: bool,
+    pub flip_y: bool,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+impl Transform {
+    pub fn new(
+        translation: Point,
+        scale: Point,
+        rotation: f32,
+        flip_x: bool,
+        flip_y: bool,
+    ) -> Self {
+        Self {
+            translation,
+            scale,
+            rotation,
+            flip_x,
+            flip_y,
+        }
+    }
+    pub fn identity() -> Self {
+        Self::new(
+            Point::new(0.0, 0.0),
+            Point::new(1.0, 1.0),
+            0.0,
+            false,
+            false,
+        )
+    }
+    pub fn translation(x: f32, y: f32) -> Self {
+        Self {
+            translation: Point::new(x, y),
+            ..Self::identity()
+        }
+    }
+    pub fn is_identity(&self) -> bool {
+        self.translation.x == 0.0
+            && self.translation.y == 0.0
+            && self.scale.x == 1.0
+            && self.scale.y == 1.0
+            && self.rotation == 0.0
+            && !self.flip_x
+            && !self.flip_y
+    }
+    // Map a local point to the parent space: scale and flip, rotate clockwise, translate
+    pub fn apply(&self, point: &Point) -> Point {
+        let (sx, sy) = self.signed_scale();
+        let (sin, cos) = self.rotation.sin_cos();
+
+        let x = point.x * sx;
+        let y = point.y * sy;
+
+        Point::new(
+            self.translation.x + x * cos - y * sin,
+            self.translation.y + x * sin + y * cos,
+        )
+    }
+    // World transform of a child with this transform as parent
+    pub fn combine(&self, local: &Transform) -> Transform {
+        let mirrored = self.flip_x != self.flip_y;
+        let rotation = if mirrored {
+            -local.rotation
+        } else {
+            local.rotation
+        };
+
+        Transform {
+            translation: self.apply(&local.translation),
+            scale: Point::new(self.scale.x * local.scale.x, self.scale.y * local.scale.y),
+            rotation: self.rotation + rotation,
+            flip_x: self.flip_x != local.flip_x,
+            flip_y: self.flip_y != local.flip_y,
+        }
+    }
+    // Axis aligned bounds of a transformed rectangle
+    pub fn bounds(&self, rectangle: &Rectangle) -> Rectangle {
+        let corners = [
+            Point::new(rectangle.x, rectangle.y),
+            Point::new(rectangle.x + rectangle.width, rectangle.y),
+            Point::new(rectangle.x, rectangle.y + rectangle.height),
+            Point::new(
+                rectangle.x + rectangle.width,
+                rectangle.y + rectangle.height,
+            ),
+        ]
+        .map(|corner| self.apply(&corner));
+
+        let x0 = corners.iter().map(|c| c.x).fold(f32::MAX, f32::min);
+        let y0 = corners.iter().map(|c| c.y).fold(f32::MAX, f32::min);
+        let x1 = corners.iter().map(|c| c.x).fold(f32::MIN, f32::max);
+        let y1 = corners.iter().map(|c| c.y).fold(f32::MIN, f32::max);
+
+        Rectangle::new(x0, y0, x1 - x0, y1 - y0)
+    }
+    // Images support scaling, flipping and quarter turn rotations, the identity borrows the image
+    pub fn image<'a>(&self, image: &'a Image) -> Cow<'a, Image> {
+        if self.is_identity() {
+            return Cow::Borrowed(image);
+        }
+
+        let quarters = (self.rotation / FRAC_PI_2).round() as i32;
+        let snapped = Transform {
+            rotation: quarters as f32 * FRAC_PI_2,
+            ..*self
+        };
+
+        let (sx, sy) = self.signed_scale();
+        let area = Rectangle::new(
+            image.x(),
+            image.y(),
+            image.width() as f32,
+            image.height() as f32,
+        );
+        let bounds = snapped.bounds(&area);
+
+        let mut result = if sx.abs() != 1.0 || sy.abs() != 1.0 {
+            image.scaled(sx.abs(), sy.abs())
+        } else {
+            image.clone()
+        };
+
+        if sx < 0.0 || sy < 0.0 {
+            result = result.flipped(sx < 0.0, sy < 0.0);
+        }
+
+        if quarters.rem_euclid(4) != 0 {
+            result = result.rotated(quarters.rem_euclid(4) as u32);
+        }
+
+        result.set_x_y(bounds.x, bounds.y);
+        Cow::Owned(result)
+    }
+    // Position a graphic given in local coordinates
+    pub fn graphic(&self, graphic: &Graphic) -> Graphic {
+        match graphic {
+            Graphic::Point { point, color } => Graphic::point(self.apply(point), *color),
+            Graphic::Rectangle { rectangle, color } => {
+                Graphic::rectangle(self.bounds(rectangle), *color)
+            }
+            Graphic::Image { image } => Graphic::image(self.image(image).into_owned()),
+            Graphic::Sprite {
+                image,
+                point,
+                rectangle,
+            } => {
+                let mut frame = image.cropped(*rectangle);
+                frame.set_x_y(point.x, point.y);
+                Graphic::image(self.image(&frame).into_owned())
+            }
+            Graphic::Indexed { image } => {
+                let mut image = image.clone();
+                let point = self.apply(&Point::new(image.x(), image.y()));
+                image.set_x_y(point.x, point.y);
+                Graphic::Indexed { image }
+            }
+            _ => graphic.clone(),
+        }
+    }
+    fn signed_scale(&self) -> (f32, f32) {
+        let sx = if self.flip_x {
+            -self.scale.x
+        } else {
+            self.scale.x
+        };
+        let sy = if self.flip_y {
+            -self.scale.y
+        } else {
+            self.scale.y
+        };
+        (sx, sy)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Node {
+    pub name: String,
+    pub graphic: Graphic,
+    pub transform: Transform,
+    pub visible: bool,
+    pub children: Vec<Node>,
+}
+
+impl Node {
+    pub fn new(name: &str, graphic: Graphic, transform: Transform) -> Self {
+        Self {
+            name: name.into(),
+            graphic,
+            transform,
+            visible: true,
+            children: Vec::new(),
+        }
+    }
+    pub fn add(&mut self, child: Node) {
+        self.children.push(child);
+    }
+    pub fn remove(&mut self, name: &str) -> Option<Node> {
+        let index = self.children.iter().position(|c| c.name == name)?;
+        Some(self.children.remove(index))
+    }
+    pub fn find(&self, name: &str) -> Option<&Node> {
+        if self.name == name {
+            return Some(self);
+        }
+        self.children.iter().find_map(|c| c.find(name))
+    }
+    pub fn find_mut(&mut self, name: &str) -> Option<&mut Node> {
+        if self.name == name {
+            return Some(self);
+        }
+        self.children.iter_mut().find_map(|c| c.find_mut(name))
+    }
+    // World transform of a descendant, including its own local transform
+    pub fn world(&self, name: &str) -> Option<Transform> {
+        self.world_from(&Transform::identity(), name)
+    }
+    // Draw list in screen space, skipping graphics outside the view
+    pub fn flatten(&self, view: &Rectangle) -> Vec<Graphic> {
+        let mut graphics = Vec::new();
+        self.collect(&Transform::identity(), view, &mut graphics);
+        graphics
+    }
+    fn world_from(&self, parent: &Transform, name: &str) -> Option<Transform> {
+        let world = parent.combine(&self.transform);
+
+        if self.name == name {
+            return Some(world);
+        }
+
+        self.children
+            .iter()
+            .find_map(|c| c.world_from(&world, name))
+    }
+    fn collect(&self, parent: &Transform, view: &Rectangle, graphics: &mut Vec<Graphic>) {
+        if !self.visible {
+            return;
+        }
+
+        let world = parent.combine(&self.transform);
+
+        match self.graphic.bounds().map(|b| world.bounds(&b)) {
+            Some(bounds) if bounds.intersects(view) => {
+                let mut graphic = world.graphic(&self.graphic);
+                let bounds = graphic.bounds().unwrap_or(bounds);
+                graphic.set_x_y(bounds.x - view.x, bounds.y - view.y);
+                graphics.push(graphic);
+            }
+            Some(_) => {}
+            None => match self.graphic {
+                Graphic::None => {}
+                _ => graphics.push(self.graphic.clone()),
+            },
+        }
+
+        for child in self.children.iter() {
+            child.collect(&world, view, graphics);
+        }
+    }
+}
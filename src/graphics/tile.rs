@@ -1,12 +1,46 @@
-use std::str;
+use std::error::Error;
+use std::fmt;
 
+use serde::Serialize;
+
+use super::image::Image;
 use super::point::Point;
 use super::rectangle::Rectangle;
 
+// Tile orientation, diagonal flips swap x and y before the horizontal and vertical flips
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct Flip {
+    pub horizontal: bool,
+    pub vertical: bool,
+    pub diagonal: bool,
+}
+
+impl Flip {
+    pub fn new(horizontal: bool, vertical: bool, diagonal: bool) -> Self {
+        Self {
+            horizontal,
+            vertical,
+            diagonal,
+        }
+    }
+    pub fn is_none(&self) -> bool {
+        !self.horizontal && !self.vertical && !self.diagonal
+    }
+    pub fn apply(&self, image: &Image) -> Image {
+        let image = match self.diagonal {
+            true => image.rotated(1).flipped(true, false),
+            false => image.clone(),
+        };
+        image.flipped(self.horizontal, self.vertical)
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct Tile {
     pub id: u32,
     pub position: Point,
     pub boundary: Rectangle,
+    pub flip: Flip,
 }
 
 impl Tile {
@@ -15,40 +49,198 @@ impl Tile {
             id,
             position,
             boundary,
+            flip: Flip::default(),
         }
     }
-    pub fn map_to_tiles(map: &str, width: u32, height: u32) -> Vec<Tile> {
-        let width: f32 = width as f32;
-        let height: f32 = height as f32;
-
-        let lines = map.split("\n");
+    // Tiles of a map where every line is a row of two character hexadecimal ids or blanks
+    pub fn map_to_tiles(map: &str, width: u32, height: u32) -> Result<Vec<Tile>, MapError> {
         let mut tiles = Vec::new();
 
-        let ascii = "Map should contain valid ASCII characters"; // 7 bits, first 128 characters
-        let hex = "Values should be in hexadecimal format";
+        for (row, line) in map.split('\n').enumerate() {
+            tiles.extend(Tile::line_to_tiles(line, row + 1, row, width, height)?);
+        }
+
+        Ok(tiles)
+    }
+    // Tiles of a single map line, number is the line in the map file used for errors
+    pub fn line_to_tiles(
+        line: &str,
+        number: usize,
+        row: usize,
+        width: u32,
+        height: u32,
+    ) -> Result<Vec<Tile>, MapError> {
+        let width = width as f32;
+        let height = height as f32;
+
+        let tiles = scan_line(line, number)?
+            .into_iter()
+            .map(|cell| {
+                let x = cell.index as f32 * width;
+                let y = row as f32 * height;
 
-        for (y, line) in lines.enumerate() {
-            for (x, value) in line
-                .as_bytes()
-                .chunks(2)
-                .map(|c| str::from_utf8(c).expect(ascii))
-                .enumerate()
-            {
-                if value != "  " {
-                    let x = x as f32 * width;
-                    let y = y as f32 * height;
+                Tile::new(
+                    cell.id,
+                    Point::new(x, y),
+                    Rectangle::new(x, y, width, height),
+                )
+            })
+            .collect();
 
-                    let id = u32::from_str_radix(&value.to_string(), 16).expect(hex);
+        Ok(tiles)
+    }
+    // Map of a level size in tiles with every row padded to the full width, joined by LF without
+    // a final newline. Maps written that way read back byte for byte, others are normalized
+    pub fn tiles_to_map(tiles: &[Tile], columns: u32, rows: u32) -> Result<String, MapError> {
+        let mut cells = vec![None; (columns * rows) as usize];
 
-                    let position = Point::new(x, y);
-                    let boundary = Rectangle::new(x, y, width, height);
+        for tile in tiles.iter() {
+            let column = (tile.position.x / tile.boundary.width).round() as i64;
+            let row = (tile.position.y / tile.boundary.height).round() as i64;
 
-                    let tile = Tile::new(id, position, boundary);
+            let line = row.max(0) as usize + 1;
+            let start = column.max(0) as usize * 2 + 1;
 
-                    tiles.push(tile);
-                }
+            if row < 0 || row >= rows as i64 {
+                return Err(MapError::new(line, start, MapErrorKind::Height(rows)));
             }
+            if column < 0 || column >= columns as i64 {
+                return Err(MapError::new(line, start, MapErrorKind::Width(columns)));
+            }
+            if tile.id > 0xff {
+                return Err(MapError::new(line, start, MapErrorKind::Range(tile.id)));
+            }
+
+            cells[(row * columns as i64 + column) as usize] = Some(tile.id);
         }
-        tiles
+
+        let lines = cells
+            .chunks(columns.max(1) as usize)
+            .map(|row| {
+                row.iter()
+                    .map(|cell| match cell {
+                        Some(id) => format!("{:02x}", id),
+                        None => "  ".into(),
+                    })
+                    .collect::<String>()
+            })
+            .collect::<Vec<String>>();
+
+        Ok(lines.join("\n"))
     }
 }
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MapErrorKind {
+    Character(char), // Non ASCII or control character
+    Hex(String),     // Cell that is not a hexadecimal id
+    Odd,             // Line ending in half a cell
+    Header(String),  // Malformed layer header field
+    Unknown(u32),    // Tile id without a texture
+    Width(u32),      // Cell right of the level, with the level width in tiles
+    Height(u32),     // Row below the level, with the level height in tiles
+    Range(u32),      // Tile id that does not fit in two hexadecimal digits
+    Entity(String),  // Malformed or missing entity field
+}
+
+// Map problem at a line and column counted from 1, tabs count as a single column
+#[derive(Debug, Clone, PartialEq)]
+pub struct MapError {
+    pub line: usize,
+    pub column: usize,
+    pub kind: MapErrorKind,
+}
+
+impl MapError {
+    pub fn new(line: usize, column: usize, kind: MapErrorKind) -> Self {
+        Self { line, column, kind }
+    }
+}
+
+impl fmt::Display for MapError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Map line {}, column {}: ", self.line, self.column)?;
+
+        match &self.kind {
+            MapErrorKind::Character(c) => write!(f, "character {:?} is not allowed", c),
+            MapErrorKind::Hex(value) => write!(f, "cell {:?} is not a hexadecimal tile id", value),
+            MapErrorKind::Odd => write!(f, "line ends in the middle of a cell"),
+            MapErrorKind::Header(field) => write!(f, "layer header field {:?} is not valid", field),
+            MapErrorKind::Unknown(id) => write!(f, "tile {:02x} is not part of the tileset", id),
+            MapErrorKind::Width(columns) => {
+                write!(f, "cell is outside the {} columns of the level", columns)
+            }
+            MapErrorKind::Height(rows) => {
+                write!(f, "row is outside the {} rows of the level", rows)
+            }
+            MapErrorKind::Range(id) => write!(f, "tile {:x} does not fit in a cell", id),
+            MapErrorKind::Entity(field) => write!(f, "entity field {:?} is not valid", field),
+        }
+    }
+}
+
+impl Error for MapError {}
+
+// Tile id of a map line with its cell index and the column it starts at
+#[derive(Debug, Copy, Clone)]
+pub struct Cell {
+    pub index: usize,
+    pub column: usize,
+    pub id: u32,
+}
+
+// Tabs advance to the next multiple of this many characters
+const TAB: usize = 8;
+
+// Cells of a map line, trailing carriage returns and blanks are ignored
+pub fn scan_line(line: &str, number: usize) -> Result<Vec<Cell>, MapError> {
+    let line = line.strip_suffix('\r').unwrap_or(line);
+
+    // Characters with the column they come from once tabs are expanded
+    let mut characters: Vec<(char, usize)> = Vec::new();
+
+    for (i, c) in line.chars().enumerate() {
+        let column = i + 1;
+
+        match c {
+            '\t' => {
+                let stop = (characters.len() / TAB + 1) * TAB;
+                characters.resize(stop, (' ', column));
+            }
+            c if c.is_ascii() && !c.is_ascii_control() => characters.push((c, column)),
+            c => return Err(MapError::new(number, column, MapErrorKind::Character(c))),
+        }
+    }
+
+    while characters.last().is_some_and(|&(c, _)| c == ' ') {
+        characters.pop();
+    }
+
+    if characters.len() % 2 == 1 {
+        let column = characters[characters.len() - 1].1;
+        return Err(MapError::new(number, column, MapErrorKind::Odd));
+    }
+
+    let mut cells = Vec::new();
+
+    for (index, pair) in characters.chunks(2).enumerate() {
+        let value = pair.iter().map(|&(c, _)| c).collect::<String>();
+        let column = pair[0].1;
+
+        if value == "  " {
+            continue;
+        }
+
+        // Checked first since parsing alone would accept signs
+        let id = match value.chars().all(|c| c.is_ascii_hexdigit()) {
+            true => u32::from_str_radix(&value, 16).ok(),
+            false => None,
+        };
+
+        let id = id.ok_or_else(|| MapError::new(number, column, MapErrorKind::Hex(value)))?;
+
+        cells.push(Cell { index, column, id });
+    }
+
+    Ok(cells)
+}
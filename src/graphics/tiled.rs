@@ -0,0 +1,1038 @@
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use flate2::read::{GzDecoder, ZlibDecoder};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::asset::{AssetError, Context};
+use super::image::Image;
+use super::layer::{Layer, Role};
+use super::point::Point;
+use super::rectangle::Rectangle;
+use super::tile::{Flip, Tile};
+
+const ASSET: &str = "Tiled map";
+
+const FLIPPED_HORIZONTALLY: u32 = 0x80000000;
+const FLIPPED_VERTICALLY: u32 = 0x40000000;
+const FLIPPED_DIAGONALLY: u32 = 0x20000000;
+const ROTATED_HEXAGONAL: u32 = 0x10000000;
+
+fn malformed(message: &str) -> AssetError {
+    AssetError::format(ASSET, message)
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum Property {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+}
+
+impl Property {
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Property::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+    pub fn as_f32(&self) -> Option<f32> {
+        match self {
+            Property::Int(value) => Some(*value as f32),
+            Property::Float(value) => Some(*value as f32),
+            _ => None,
+        }
+    }
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Property::String(value) => Some(value),
+            _ => None,
+        }
+    }
+    // Typed value from its textual form, colors, files and objects stay strings
+    fn parse(kind: &str, value: &str) -> Result<Self, AssetError> {
+        let invalid =
+            || AssetError::format(ASSET, format!("property value {} is not a {}", value, kind));
+
+        Ok(match kind {
+            "bool" => Property::Bool(value.parse().map_err(|_| invalid())?),
+            "int" => Property::Int(value.parse().map_err(|_| invalid())?),
+            "float" => Property::Float(value.parse().map_err(|_| invalid())?),
+            _ => Property::String(value.into()),
+        })
+    }
+    fn from_json(kind: &str, value: &Value) -> Result<Self, AssetError> {
+        match value {
+            Value::Bool(value) => Ok(Property::Bool(*value)),
+            Value::Number(number) if kind == "int" || kind == "object" => number
+                .as_i64()
+                .map(Property::Int)
+                .ok_or_else(|| malformed("integer property is out of range")),
+            Value::Number(number) => Ok(Property::Float(number.as_f64().unwrap_or_default())),
+            Value::String(value) => Property::parse(kind, value),
+            _ => Err(malformed("property values should be scalars")),
+        }
+    }
+}
+
+pub type Properties = BTreeMap<String, Property>;
+
+#[derive(Debug, Clone, Default)]
+pub struct Tileset {
+    pub first: u32,
+    pub name: String,
+    pub tile_width: u32,
+    pub tile_height: u32,
+    pub columns: u32,
+    pub count: u32,
+    pub margin: u32,
+    pub spacing: u32,
+    pub image: PathBuf,
+    pub tiles: BTreeMap<u32, Properties>,
+}
+
+impl Tileset {
+    pub fn contains(&self, gid: u32) -> bool {
+        gid >= self.first && gid < self.first + self.count
+    }
+    // Textures keyed by global tile id
+    pub fn textures(&self, sheet: &mut Image) -> BTreeMap<u32, Image> {
+        let indices = (0..self.count).collect::<Vec<u32>>();
+
+        // Columns left out of the tileset are counted from the sheet image
+        let columns = match self.columns {
+            0 => Image::sheet_columns(sheet, self.tile_width, self.margin, self.spacing),
+            columns => columns,
+        };
+
+        let images = Image::grid_to_texture_list(
+            sheet,
+            self.tile_width,
+            self.tile_height,
+            self.margin,
+            self.spacing,
+            columns,
+            &indices,
+        );
+
+        images
+            .into_iter()
+            .enumerate()
+            .map(|(index, image)| (self.first + index as u32, image))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TileLayer {
+    pub name: String,
+    pub width: u32,
+    pub height: u32,
+    pub visible: bool,
+    pub opacity: f32,
+    pub offset: Point,
+    pub parallax: Point,
+    pub data: Vec<u32>,
+    pub properties: Properties,
+}
+
+impl TileLayer {
+    // Global tile id and flip flags at a cell, zero is an empty cell
+    pub fn gid(&self, column: u32, row: u32) -> (u32, Flip) {
+        let raw = self.data[(row * self.width + column) as usize];
+        split_gid(raw)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Object {
+    pub id: u32,
+    pub name: String,
+    pub kind: String,
+    pub bounds: Rectangle,
+    pub rotation: f32,
+    pub gid: Option<u32>,
+    pub visible: bool,
+    pub properties: Properties,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ObjectLayer {
+    pub name: String,
+    pub visible: bool,
+    pub objects: Vec<Object>,
+    pub properties: Properties,
+}
+
+// Orthogonal finite map, group layers are flattened in drawing order
+#[derive(Debug, Clone, Default)]
+pub struct Map {
+    pub width: u32,
+    pub height: u32,
+    pub tile_width: u32,
+    pub tile_height: u32,
+    pub tilesets: Vec<Tileset>,
+    pub layers: Vec<TileLayer>,
+    pub groups: Vec<ObjectLayer>,
+    pub properties: Properties,
+}
+
+impl Map {
+    // Tiled XML (.tmx) or JSON (.tmj, .json) by file extension
+    pub fn from_uri(uri: &str) -> Result<Self, AssetError> {
+        let text = std::fs::read_to_string(uri).context(ASSET)?;
+        let directory = Path::new(uri).parent().unwrap_or(Path::new(""));
+
+        match Path::new(uri).extension().and_then(|e| e.to_str()) {
+            Some("tmx") | Some("xml") => Self::parse_tmx(&text, Some(directory)),
+            Some("tmj") | Some("json") => Self::parse_tmj(&text, Some(directory)),
+            _ => Err(AssetError::format(
+                ASSET,
+                format!("unknown map format of {}", uri),
+            )),
+        }
+    }
+    // Without a directory, tilesets have to be embedded in the map
+    pub fn from_tmx(xml: &str) -> Result<Self, AssetError> {
+        Self::parse_tmx(xml, None)
+    }
+    pub fn from_tmj(json: &str) -> Result<Self, AssetError> {
+        Self::parse_tmj(json, None)
+    }
+    pub fn pixel_width(&self) -> u32 {
+        self.width * self.tile_width
+    }
+    pub fn pixel_height(&self) -> u32 {
+        self.height * self.tile_height
+    }
+    pub fn layer(&self, name: &str) -> Option<&TileLayer> {
+        self.layers.iter().find(|l| l.name == name)
+    }
+    pub fn tileset(&self, gid: u32) -> Option<&Tileset> {
+        self.tilesets.iter().find(|t| t.contains(gid))
+    }
+    pub fn tile_properties(&self, gid: u32) -> Option<&Properties> {
+        let tileset = self.tileset(gid)?;
+        tileset.tiles.get(&(gid - tileset.first))
+    }
+    pub fn objects(&self) -> Vec<&Object> {
+        self.groups.iter().flat_map(|g| g.objects.iter()).collect()
+    }
+    pub fn object(&self, name: &str) -> Option<&Object> {
+        self.groups
+            .iter()
+            .flat_map(|g| g.objects.iter())
+            .find(|o| o.name == name)
+    }
+    // Tiles of a layer with ids being global tile ids
+    pub fn layer_tiles(&self, layer: &TileLayer) -> Vec<Tile> {
+        let width = self.tile_width as f32;
+        let height = self.tile_height as f32;
+
+        let mut tiles = Vec::new();
+
+        for row in 0..layer.height {
+            for column in 0..layer.width {
+                let (gid, flip) = layer.gid(column, row);
+
+                if gid == 0 {
+                    continue;
+                }
+
+                let x = column as f32 * width + layer.offset.x;
+                let y = row as f32 * height + layer.offset.y;
+
+                let position = Point::new(x, y);
+                let boundary = Rectangle::new(x, y, width, height);
+
+                let mut tile = Tile::new(gid, position, boundary);
+                tile.flip = flip;
+
+                tiles.push(tile);
+            }
+        }
+
+        tiles
+    }
+    // Tiles of all visible layers
+    pub fn tiles(&self) -> Vec<Tile> {
+        self.layers
+            .iter()
+            .filter(|l| l.visible)
+            .flat_map(|l| self.layer_tiles(l))
+            .collect()
+    }
+    // Visible layers with their role taken from a role property, collision by default
+    pub fn level_layers(&self) -> Result<Vec<Layer>, AssetError> {
+        let mut layers = Vec::new();
+
+        for layer in self.layers.iter().filter(|l| l.visible) {
+            let role = match layer.properties.get("role") {
+                Some(Property::String(role)) => Role::parse(role).ok_or_else(|| {
+                    AssetError::format(
+                        ASSET,
+                        format!("layer {} has an unknown role {}", layer.name, role),
+                    )
+                })?,
+                Some(_) => return Err(malformed("layer role should be a string")),
+                None => Role::Collision,
+            };
+
+            let mut level = Layer::new(&layer.name, role, self.layer_tiles(layer));
+            level.opacity = layer.opacity;
+            level.parallax = layer.parallax;
+
+            layers.push(level);
+        }
+
+        Ok(layers)
+    }
+    // Load tileset images relative to a directory and cut them into textures
+    pub fn textures(&self, directory: &Path) -> Result<BTreeMap<u32, Image>, AssetError> {
+        let mut textures = BTreeMap::new();
+
+        for tileset in self.tilesets.iter() {
+            let path = directory.join(&tileset.image);
+            let path = path
+                .to_str()
+                .ok_or_else(|| malformed("tileset image path is not valid UTF-8"))?;
+
+            let mut sheet = Image::from_uri(0.0, 0.0, path).context(ASSET)?;
+            textures.append(&mut tileset.textures(&mut sheet));
+        }
+
+        Ok(textures)
+    }
+    fn parse_tmx(xml: &str, directory: Option<&Path>) -> Result<Self, AssetError> {
+        let document = roxmltree::Document::parse(xml).context(ASSET)?;
+        let root = document.root_element();
+
+        if root.tag_name().name() != "map" {
+            return Err(malformed("root element should be a map"));
+        }
+
+        check_layout(
+            root.attribute("orientation").unwrap_or("orthogonal"),
+            root.attribute("infinite") == Some("1"),
+        )?;
+
+        let mut map = Self {
+            width: attribute(&root, "width")?,
+            height: attribute(&root, "height")?,
+            tile_width: attribute(&root, "tilewidth")?,
+            tile_height: attribute(&root, "tileheight")?,
+            ..Self::default()
+        };
+
+        for node in root.children().filter(|n| n.is_element()) {
+            match node.tag_name().name() {
+                "properties" => map.properties = xml_properties(&node)?,
+                "tileset" => {
+                    let first = attribute(&node, "firstgid")?;
+                    let tileset = match node.attribute("source") {
+                        Some(source) => external_tileset(source, first, directory)?,
+                        None => xml_tileset(&node, first, Path::new(""))?,
+                    };
+                    map.tilesets.push(tileset);
+                }
+                _ => {}
+            }
+        }
+
+        map.xml_layers(&root, Inherited::default())?;
+
+        Ok(map)
+    }
+    fn xml_layers(
+        &mut self,
+        parent: &roxmltree::Node,
+        inherited: Inherited,
+    ) -> Result<(), AssetError> {
+        for node in parent.children().filter(|n| n.is_element()) {
+            let name = node.attribute("name").unwrap_or_default().to_string();
+            let inherited = inherited.nest(
+                node.attribute("visible") != Some("0"),
+                Point::new(
+                    optional(&node, "offsetx")?.unwrap_or(0.0),
+                    optional(&node, "offsety")?.unwrap_or(0.0),
+                ),
+                Point::new(
+                    optional(&node, "parallaxx")?.unwrap_or(1.0),
+                    optional(&node, "parallaxy")?.unwrap_or(1.0),
+                ),
+            );
+            let Inherited {
+                visible: shown,
+                offset,
+                parallax,
+            } = inherited;
+            let properties = match child(&node, "properties") {
+                Some(properties) => xml_properties(&properties)?,
+                None => Properties::new(),
+            };
+
+            match node.tag_name().name() {
+                "layer" => {
+                    let width = attribute(&node, "width")?;
+                    let height = attribute(&node, "height")?;
+                    let data =
+                        child(&node, "data").ok_or_else(|| malformed("layer has no data"))?;
+
+                    self.layers.push(TileLayer {
+                        name,
+                        width,
+                        height,
+                        visible: shown,
+                        opacity: optional(&node, "opacity")?.unwrap_or(1.0),
+                        offset,
+                        parallax,
+                        data: xml_data(&data, width * height)?,
+                        properties,
+                    });
+                }
+                "objectgroup" => {
+                    let mut objects = Vec::new();
+
+                    for object in node.children().filter(|n| n.has_tag_name("object")) {
+                        objects.push(xml_object(&object)?);
+                    }
+
+                    self.groups.push(ObjectLayer {
+                        name,
+                        visible: shown,
+                        objects,
+                        properties,
+                    });
+                }
+                "group" => self.xml_layers(&node, inherited)?,
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+    fn parse_tmj(json: &str, directory: Option<&Path>) -> Result<Self, AssetError> {
+        let data: JsonMap = serde_json::from_str(json).context(ASSET)?;
+
+        check_layout(&data.orientation, data.infinite)?;
+
+        let mut map = Self {
+            width: data.width,
+            height: data.height,
+            tile_width: data.tilewidth,
+            tile_height: data.tileheight,
+            properties: json_properties(&data.properties)?,
+            ..Self::default()
+        };
+
+        for tileset in data.tilesets.iter() {
+            let tileset = match tileset.source.as_ref() {
+                Some(source) => external_tileset(source, tileset.firstgid, directory)?,
+                None => json_tileset(tileset, tileset.firstgid, Path::new(""))?,
+            };
+            map.tilesets.push(tileset);
+        }
+
+        map.json_layers(&data.layers, Inherited::default())?;
+
+        Ok(map)
+    }
+    fn json_layers(
+        &mut self,
+        layers: &[JsonLayer],
+        inherited: Inherited,
+    ) -> Result<(), AssetError> {
+        for layer in layers.iter() {
+            let inherited = inherited.nest(
+                layer.visible,
+                Point::new(layer.offsetx, layer.offsety),
+                Point::new(layer.parallaxx, layer.parallaxy),
+            );
+            let Inherited {
+                visible: shown,
+                offset,
+                parallax,
+            } = inherited;
+            let properties = json_properties(&layer.properties)?;
+
+            match layer.kind.as_str() {
+                "tilelayer" => {
+                    let count = layer.width * layer.height;
+
+                    let data = match layer.data.as_ref() {
+                        Some(JsonData::Array(data)) => data.clone(),
+                        Some(JsonData::Encoded(text)) => decode(
+                            text,
+                            layer.encoding.as_deref(),
+                            layer.compression.as_deref(),
+                            count,
+                        )?,
+                        None => return Err(malformed("tile layer has no data")),
+                    };
+
+                    if data.len() != count as usize {
+                        return Err(AssetError::format(
+                            ASSET,
+                            format!(
+                                "layer {} has {} cells instead of {}",
+                                layer.name,
+                                data.len(),
+                                count
+                            ),
+                        ));
+                    }
+
+                    self.layers.push(TileLayer {
+                        name: layer.name.clone(),
+                        width: layer.width,
+                        height: layer.height,
+                        visible: shown,
+                        opacity: layer.opacity,
+                        offset,
+                        parallax,
+                        data,
+                        properties,
+                    });
+                }
+                "objectgroup" => {
+                    let mut objects = Vec::new();
+
+                    for object in layer.objects.iter() {
+                        objects.push(Object {
+                            id: object.id,
+                            name: object.name.clone(),
+                            kind: object.class.clone().unwrap_or(object.kind.clone()),
+                            bounds: Rectangle::new(object.x, object.y, object.width, object.height),
+                            rotation: object.rotation,
+                            gid: object.gid.map(|gid| split_gid(gid).0),
+                            visible: object.visible,
+                            properties: json_properties(&object.properties)?,
+                        });
+                    }
+
+                    self.groups.push(ObjectLayer {
+                        name: layer.name.clone(),
+                        visible: shown,
+                        objects,
+                        properties,
+                    });
+                }
+                "group" => self.json_layers(&layer.layers, inherited)?,
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// Attributes that group layers pass on to their children
+#[derive(Copy, Clone)]
+struct Inherited {
+    visible: bool,
+    offset: Point,
+    parallax: Point,
+}
+
+impl Default for Inherited {
+    fn default() -> Self {
+        Self {
+            visible: true,
+            offset: Point::new(0.0, 0.0),
+            parallax: Point::new(1.0, 1.0),
+        }
+    }
+}
+
+impl Inherited {
+    fn nest(&self, visible: bool, offset: Point, parallax: Point) -> Self {
+        Self {
+            visible: self.visible && visible,
+            offset: Point::new(self.offset.x + offset.x, self.offset.y + offset.y),
+            parallax: Point::new(self.parallax.x * parallax.x, self.parallax.y * parallax.y),
+        }
+    }
+}
+
+// Strip flip flags from a global tile id
+pub fn split_gid(raw: u32) -> (u32, Flip) {
+    let flip = Flip::new(
+        raw & FLIPPED_HORIZONTALLY != 0,
+        raw & FLIPPED_VERTICALLY != 0,
+        raw & FLIPPED_DIAGONALLY != 0,
+    );
+
+    let gid =
+        raw & !(FLIPPED_HORIZONTALLY | FLIPPED_VERTICALLY | FLIPPED_DIAGONALLY | ROTATED_HEXAGONAL);
+
+    (gid, flip)
+}
+
+fn check_layout(orientation: &str, infinite: bool) -> Result<(), AssetError> {
+    if orientation != "orthogonal" {
+        return Err(AssetError::format(
+            ASSET,
+            format!("{} maps are not supported", orientation),
+        ));
+    }
+    if infinite {
+        return Err(malformed("infinite maps are not supported"));
+    }
+    Ok(())
+}
+
+// Cell data as CSV or base64 with optional zlib or gzip compression
+fn decode(
+    text: &str,
+    encoding: Option<&str>,
+    compression: Option<&str>,
+    count: u32,
+) -> Result<Vec<u32>, AssetError> {
+    let data = match encoding {
+        Some("csv") => text
+            .split(',')
+            .map(|v| v.trim())
+            .filter(|v| !v.is_empty())
+            .map(|v| {
+                v.parse::<u32>()
+                    .map_err(|_| malformed("CSV cell is not a tile id"))
+            })
+            .collect::<Result<Vec<u32>, AssetError>>()?,
+        Some("base64") => {
+            let compact = text.split_whitespace().collect::<String>();
+            let bytes = STANDARD
+                .decode(compact)
+                .map_err(|e| AssetError::format(ASSET, format!("invalid base64 data: {}", e)))?;
+
+            let bytes = match compression {
+                None | Some("") => bytes,
+                Some("zlib") => {
+                    let mut inflated = Vec::new();
+                    ZlibDecoder::new(&bytes[..])
+                        .read_to_end(&mut inflated)
+                        .context(ASSET)?;
+                    inflated
+                }
+                Some("gzip") => {
+                    let mut inflated = Vec::new();
+                    GzDecoder::new(&bytes[..])
+                        .read_to_end(&mut inflated)
+                        .context(ASSET)?;
+                    inflated
+                }
+                Some(other) => {
+                    return Err(AssetError::format(
+                        ASSET,
+                        format!("{} compression is not supported", other),
+                    ))
+                }
+            };
+
+            if bytes.len() % 4 != 0 {
+                return Err(malformed("base64 data is not a list of 32 bit tile ids"));
+            }
+
+            bytes
+                .chunks(4)
+                .map(|c| u32::from_le_bytes([c[0], c[1], c[2], c[3]]))
+                .collect()
+        }
+        other => {
+            return Err(AssetError::format(
+                ASSET,
+                format!("{} encoding is not supported", other.unwrap_or("unknown")),
+            ))
+        }
+    };
+
+    if data.len() != count as usize {
+        return Err(AssetError::format(
+            ASSET,
+            format!("layer has {} cells instead of {}", data.len(), count),
+        ));
+    }
+
+    Ok(data)
+}
+
+fn attribute<T: std::str::FromStr>(node: &roxmltree::Node, name: &str) -> Result<T, AssetError> {
+    optional(node, name)?.ok_or_else(|| {
+        AssetError::format(
+            ASSET,
+            format!(
+                "{} element is missing the {} attribute",
+                node.tag_name().name(),
+                name
+            ),
+        )
+    })
+}
+
+fn optional<T: std::str::FromStr>(
+    node: &roxmltree::Node,
+    name: &str,
+) -> Result<Option<T>, AssetError> {
+    match node.attribute(name) {
+        Some(value) => value.parse().map(Some).map_err(|_| {
+            AssetError::format(
+                ASSET,
+                format!(
+                    "{} attribute {} has an invalid value {}",
+                    node.tag_name().name(),
+                    name,
+                    value
+                ),
+            )
+        }),
+        None => Ok(None),
+    }
+}
+
+fn child<'a, 'input>(
+    node: &roxmltree::Node<'a, 'input>,
+    name: &str,
+) -> Option<roxmltree::Node<'a, 'input>> {
+    node.children().find(|n| n.has_tag_name(name))
+}
+
+fn xml_properties(node: &roxmltree::Node) -> Result<Properties, AssetError> {
+    let mut properties = Properties::new();
+
+    for property in node.children().filter(|n| n.has_tag_name("property")) {
+        let name = property.attribute("name").unwrap_or_default();
+        let kind = property.attribute("type").unwrap_or("string");
+        // Multiline strings are stored as text content
+        let value = property
+            .attribute("value")
+            .or_else(|| property.text())
+            .unwrap_or_default();
+
+        properties.insert(name.into(), Property::parse(kind, value)?);
+    }
+
+    Ok(properties)
+}
+
+fn xml_data(node: &roxmltree::Node, count: u32) -> Result<Vec<u32>, AssetError> {
+    match node.attribute("encoding") {
+        Some(encoding) => decode(
+            node.text().unwrap_or_default(),
+            Some(encoding),
+            node.attribute("compression"),
+            count,
+        ),
+        None => {
+            let mut data = Vec::with_capacity(count as usize);
+
+            for tile in node.children().filter(|n| n.has_tag_name("tile")) {
+                data.push(optional(&tile, "gid")?.unwrap_or(0));
+            }
+
+            if data.len() != count as usize {
+                return Err(malformed("layer has a wrong number of tile elements"));
+            }
+
+            Ok(data)
+        }
+    }
+}
+
+fn xml_object(node: &roxmltree::Node) -> Result<Object, AssetError> {
+    let kind = node
+        .attribute("class")
+        .or_else(|| node.attribute("type"))
+        .unwrap_or_default();
+
+    let properties = match child(node, "properties") {
+        Some(properties) => xml_properties(&properties)?,
+        None => Properties::new(),
+    };
+
+    Ok(Object {
+        id: optional(node, "id")?.unwrap_or(0),
+        name: node.attribute("name").unwrap_or_default().into(),
+        kind: kind.into(),
+        bounds: Rectangle::new(
+            optional(node, "x")?.unwrap_or(0.0),
+            optional(node, "y")?.unwrap_or(0.0),
+            optional(node, "width")?.unwrap_or(0.0),
+            optional(node, "height")?.unwrap_or(0.0),
+        ),
+        rotation: optional(node, "rotation")?.unwrap_or(0.0),
+        gid: optional::<u32>(node, "gid")?.map(|gid| split_gid(gid).0),
+        visible: node.attribute("visible") != Some("0"),
+        properties,
+    })
+}
+
+// Image paths are made relative to the map directory
+fn xml_tileset(node: &roxmltree::Node, first: u32, base: &Path) -> Result<Tileset, AssetError> {
+    let image = child(node, "image")
+        .ok_or_else(|| malformed("tilesets without a single image are not supported"))?;
+    let source = image
+        .attribute("source")
+        .ok_or_else(|| malformed("tileset image has no source"))?;
+
+    let mut tiles = BTreeMap::new();
+
+    for tile in node.children().filter(|n| n.has_tag_name("tile")) {
+        if let Some(properties) = child(&tile, "properties") {
+            tiles.insert(attribute(&tile, "id")?, xml_properties(&properties)?);
+        }
+    }
+
+    let tile_width = attribute(node, "tilewidth")?;
+    let tile_height = attribute(node, "tileheight")?;
+    let margin = optional(node, "margin")?.unwrap_or(0);
+    let spacing = optional(node, "spacing")?.unwrap_or(0);
+
+    if tile_width == 0 || tile_height == 0 {
+        return Err(malformed("tileset tile size should not be zero"));
+    }
+
+    let columns = match optional(node, "columns")? {
+        Some(columns) => columns,
+        None => {
+            let width = attribute::<u32>(&image, "width")?;
+            (width + spacing).saturating_sub(margin * 2) / (tile_width + spacing)
+        }
+    };
+
+    Ok(Tileset {
+        first,
+        name: node.attribute("name").unwrap_or_default().into(),
+        tile_width,
+        tile_height,
+        columns,
+        count: attribute(node, "tilecount")?,
+        margin,
+        spacing,
+        image: base.join(source),
+        tiles,
+    })
+}
+
+fn json_tileset(data: &JsonTileset, first: u32, base: &Path) -> Result<Tileset, AssetError> {
+    let image = data
+        .image
+        .as_ref()
+        .ok_or_else(|| malformed("tilesets without a single image are not supported"))?;
+
+    if data.tilewidth == 0 || data.tileheight == 0 {
+        return Err(malformed("tileset tile size should not be zero"));
+    }
+
+    let columns = match data.columns {
+        0 => {
+            (data.imagewidth + data.spacing).saturating_sub(data.margin * 2)
+                / (data.tilewidth + data.spacing)
+        }
+        columns => columns,
+    };
+
+    let mut tiles = BTreeMap::new();
+
+    for tile in data.tiles.iter() {
+        if !tile.properties.is_empty() {
+            tiles.insert(tile.id, json_properties(&tile.properties)?);
+        }
+    }
+
+    Ok(Tileset {
+        first,
+        name: data.name.clone(),
+        tile_width: data.tilewidth,
+        tile_height: data.tileheight,
+        columns,
+        count: data.tilecount,
+        margin: data.margin,
+        spacing: data.spacing,
+        image: base.join(image),
+        tiles,
+    })
+}
+
+fn external_tileset(
+    source: &str,
+    first: u32,
+    directory: Option<&Path>,
+) -> Result<Tileset, AssetError> {
+    let directory = directory.ok_or_else(|| {
+        malformed("external tilesets can only be loaded together with the map file")
+    })?;
+
+    let path = directory.join(source);
+    let text = std::fs::read_to_string(&path).context(ASSET)?;
+    let base = Path::new(source).parent().unwrap_or(Path::new(""));
+
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("tsj") | Some("json") => {
+            json_tileset(&serde_json::from_str(&text).context(ASSET)?, first, base)
+        }
+        _ => {
+            let document = roxmltree::Document::parse(&text).context(ASSET)?;
+            xml_tileset(&document.root_element(), first, base)
+        }
+    }
+}
+
+fn json_properties(properties: &[JsonProperty]) -> Result<Properties, AssetError> {
+    properties
+        .iter()
+        .map(|p| Ok((p.name.clone(), Property::from_json(&p.kind, &p.value)?)))
+        .collect()
+}
+
+#[derive(Deserialize)]
+struct JsonMap {
+    width: u32,
+    height: u32,
+    tilewidth: u32,
+    tileheight: u32,
+    #[serde(default = "orthogonal")]
+    orientation: String,
+    #[serde(default)]
+    infinite: bool,
+    #[serde(default)]
+    tilesets: Vec<JsonTileset>,
+    #[serde(default)]
+    layers: Vec<JsonLayer>,
+    #[serde(default)]
+    properties: Vec<JsonProperty>,
+}
+
+#[derive(Deserialize)]
+struct JsonTileset {
+    #[serde(default)]
+    firstgid: u32,
+    #[serde(default)]
+    source: Option<String>,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    tilewidth: u32,
+    #[serde(default)]
+    tileheight: u32,
+    #[serde(default)]
+    columns: u32,
+    #[serde(default)]
+    tilecount: u32,
+    #[serde(default)]
+    margin: u32,
+    #[serde(default)]
+    spacing: u32,
+    #[serde(default)]
+    image: Option<String>,
+    #[serde(default)]
+    imagewidth: u32,
+    #[serde(default)]
+    tiles: Vec<JsonTile>,
+}
+
+#[derive(Deserialize)]
+struct JsonTile {
+    id: u32,
+    #[serde(default)]
+    properties: Vec<JsonProperty>,
+}
+
+#[derive(Deserialize)]
+struct JsonLayer {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    width: u32,
+    #[serde(default)]
+    height: u32,
+    #[serde(default = "shown")]
+    visible: bool,
+    #[serde(default = "opaque")]
+    opacity: f32,
+    #[serde(default)]
+    offsetx: f32,
+    #[serde(default)]
+    offsety: f32,
+    #[serde(default = "opaque")]
+    parallaxx: f32,
+    #[serde(default = "opaque")]
+    parallaxy: f32,
+    #[serde(default)]
+    data: Option<JsonData>,
+    #[serde(default)]
+    encoding: Option<String>,
+    #[serde(default)]
+    compression: Option<String>,
+    #[serde(default)]
+    objects: Vec<JsonObject>,
+    #[serde(default)]
+    layers: Vec<JsonLayer>,
+    #[serde(default)]
+    properties: Vec<JsonProperty>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonData {
+    Array(Vec<u32>),
+    Encoded(String),
+}
+
+#[derive(Deserialize)]
+struct JsonObject {
+    #[serde(default)]
+    id: u32,
+    #[serde(default)]
+    name: String,
+    #[serde(default, rename = "type")]
+    kind: String,
+    #[serde(default)]
+    class: Option<String>,
+    #[serde(default)]
+    x: f32,
+    #[serde(default)]
+    y: f32,
+    #[serde(default)]
+    width: f32,
+    #[serde(default)]
+    height: f32,
+    #[serde(default)]
+    rotation: f32,
+    #[serde(default)]
+    gid: Option<u32>,
+    #[serde(default = "shown")]
+    visible: bool,
+    #[serde(default)]
+    properties: Vec<JsonProperty>,
+}
+
+#[derive(Deserialize)]
+struct JsonProperty {
+    name: String,
+    #[serde(default = "string", rename = "type")]
+    kind: String,
+    value: Value,
+}
+
+fn orthogonal() -> String {
+    "orthogonal".into()
+}
+
+fn string() -> String {
+    "string".into()
+}
+
+fn shown() -> bool {
+    true
+}
+
+fn opaque() -> f32 {
+    1.0
+}
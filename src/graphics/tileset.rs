@@ -0,0 +1,356 @@
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+
+use super::animation::{Animation, Frame, Mode};
+use super::asset::{AssetError, Context};
+use super::autotile::Terrain;
+use super::image::Image;
+use super::rectangle::Rectangle;
+use super::tiled::Properties;
+
+const ASSET: &str = "Tileset file";
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Collision {
+    #[default]
+    Solid,
+    OneWay,
+    None,
+}
+
+impl Collision {
+    pub fn parse(collision: &str) -> Option<Collision> {
+        match collision {
+            "solid" => Some(Collision::Solid),
+            "oneway" => Some(Collision::OneWay),
+            "none" => Some(Collision::None),
+            _ => None,
+        }
+    }
+}
+
+// Linear heightfield of a slope tile, heights are fractions of the tile height measured
+// from the bottom edge, or from the top edge for ceiling slopes
+#[derive(Debug, Copy, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Slope {
+    pub left: f32,
+    pub right: f32,
+    pub ceiling: bool,
+}
+
+impl Slope {
+    pub fn new(left: f32, right: f32, ceiling: bool) -> Self {
+        Self {
+            left,
+            right,
+            ceiling,
+        }
+    }
+    pub fn height(&self, t: f32) -> f32 {
+        self.left + (self.right - self.left) * t.clamp(0.0, 1.0)
+    }
+    // Level y of the solid edge at level x
+    pub fn surface(&self, tile: &Rectangle, x: f32) -> f32 {
+        let height = self.height((x - tile.x) / tile.width) * tile.height;
+
+        match self.ceiling {
+            true => tile.y + height,
+            false => tile.y + tile.height - height,
+        }
+    }
+    // Change of the surface y per level x
+    pub fn gradient(&self, tile: &Rectangle) -> f32 {
+        let rise = (self.right - self.left) * tile.height / tile.width;
+
+        match self.ceiling {
+            true => rise,
+            false => -rise,
+        }
+    }
+    // Solid height of the left or right edge in level units
+    pub fn edge(&self, tile: &Rectangle, right: bool) -> f32 {
+        match right {
+            true => self.right * tile.height,
+            false => self.left * tile.height,
+        }
+    }
+    // Texture cut to the solid part of the tile
+    pub fn mask(&self, image: &Image) -> Image {
+        let mut image = image.clone();
+
+        let width = image.width() as usize;
+        let height = image.height() as f32;
+
+        for (i, pixel) in image.bytes_mut().chunks_exact_mut(4).enumerate() {
+            let x = (i % width) as f32 + 0.5;
+            let y = (i / width) as f32 + 0.5;
+
+            let solid = self.height(x / width as f32) * height;
+
+            let inside = match self.ceiling {
+                true => y < solid,
+                false => height - y < solid,
+            };
+
+            if !inside {
+                pixel.copy_from_slice(&[0, 0, 0, 0]);
+            }
+        }
+
+        image
+    }
+}
+
+// Collision behaviour of a tile, one-way tiles only stop falling onto their top edge
+#[derive(Debug, Copy, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TileProperties {
+    pub collision: Collision,
+    pub damage: f32,
+    pub restitution: f32,
+    pub friction: Option<f32>,
+    pub climbable: bool,
+    pub slope: Option<Slope>,
+}
+
+impl TileProperties {
+    pub fn solid(&self) -> bool {
+        self.collision == Collision::Solid
+    }
+    pub fn hazard(&self) -> bool {
+        self.damage > 0.0
+    }
+    pub fn bouncy(&self) -> bool {
+        self.restitution > 0.0
+    }
+    // Custom properties of a Tiled tile, unset properties keep their defaults
+    pub fn from_properties(properties: &Properties) -> Result<Self, AssetError> {
+        let mut tile = Self::default();
+        let mut slope = Slope::default();
+
+        let invalid = |name: &str| {
+            AssetError::format(ASSET, format!("tile property {} has the wrong type", name))
+        };
+
+        for (name, value) in properties.iter() {
+            match name.as_str() {
+                "collision" => {
+                    let collision = value.as_str().ok_or_else(|| invalid(name))?;
+                    tile.collision = Collision::parse(collision).ok_or_else(|| {
+                        AssetError::format(ASSET, format!("unknown collision {}", collision))
+                    })?;
+                }
+                "damage" => tile.damage = value.as_f32().ok_or_else(|| invalid(name))?,
+                "restitution" => tile.restitution = value.as_f32().ok_or_else(|| invalid(name))?,
+                "friction" => tile.friction = Some(value.as_f32().ok_or_else(|| invalid(name))?),
+                "climbable" => tile.climbable = value.as_bool().ok_or_else(|| invalid(name))?,
+                "slope_left" => slope.left = value.as_f32().ok_or_else(|| invalid(name))?,
+                "slope_right" => slope.right = value.as_f32().ok_or_else(|| invalid(name))?,
+                "ceiling" => slope.ceiling = value.as_bool().ok_or_else(|| invalid(name))?,
+                _ => (),
+            }
+        }
+
+        let shaped = ["slope_left", "slope_right", "ceiling"];
+
+        if shaped.iter().any(|name| properties.contains_key(*name)) {
+            tile.slope = Some(slope);
+        }
+
+        Ok(tile)
+    }
+}
+
+// Frame sequence a tile is drawn with, frames are tile ids shown for their duration
+#[derive(Debug, Clone, Default)]
+pub struct TileAnimation {
+    pub frames: Vec<u32>,
+    pub durations: Vec<f32>,
+    pub mode: Mode,
+}
+
+impl TileAnimation {
+    // Animation of the frame textures, none when a frame has no texture
+    pub fn animation(&self, textures: &BTreeMap<u32, Image>) -> Option<Animation> {
+        let frames = self
+            .frames
+            .iter()
+            .zip(self.durations.iter())
+            .map(|(id, &duration)| Some(Frame::new(textures.get(id)?.clone(), duration)))
+            .collect::<Option<Vec<Frame>>>()?;
+
+        if frames.is_empty() {
+            return None;
+        }
+
+        let mut animation = Animation::from_frames(frames);
+        animation.set_mode(self.mode);
+        animation.restart();
+
+        Some(animation)
+    }
+}
+
+// Properties by tile id, tiles missing from the table get the default properties
+#[derive(Debug, Clone, Default)]
+pub struct Tileset {
+    default: TileProperties,
+    tiles: BTreeMap<u32, TileProperties>,
+    pub terrains: Vec<Terrain>,
+    pub animations: BTreeMap<u32, TileAnimation>,
+}
+
+impl Tileset {
+    pub fn new() -> Self {
+        Self {
+            default: TileProperties::default(),
+            tiles: BTreeMap::new(),
+            terrains: Vec::new(),
+            animations: BTreeMap::new(),
+        }
+    }
+    pub fn from_uri(uri: &str) -> Result<Self, AssetError> {
+        Self::from_json(&std::fs::read_to_string(uri).context(ASSET)?)
+    }
+    // Tile ids are the hexadecimal values used in map files
+    pub fn from_json(json: &str) -> Result<Self, AssetError> {
+        let data: Definition = serde_json::from_str(json).context(ASSET)?;
+
+        let mut tileset = Self::new();
+        tileset.default = data.default;
+
+        for (key, properties) in data.tiles.into_iter() {
+            tileset.set(parse_id(&key)?, properties);
+        }
+
+        for definition in data.terrains.into_iter() {
+            if definition.mask != 4 && definition.mask != 8 {
+                return Err(AssetError::format(
+                    ASSET,
+                    format!("terrain {} mask should be 4 or 8 bits", definition.name),
+                ));
+            }
+
+            let mut terrain = Terrain::new(&definition.name, definition.mask);
+
+            for (key, id) in definition.tiles.iter() {
+                let mask = Terrain::parse_mask(key).ok_or_else(|| {
+                    AssetError::format(ASSET, format!("terrain mask {} is not valid", key))
+                })?;
+                terrain.rules.insert(mask, parse_id(id)?);
+            }
+
+            terrain.default = definition.default.as_deref().map(parse_id).transpose()?;
+
+            tileset.terrains.push(terrain);
+        }
+
+        for (key, definition) in data.animations.into_iter() {
+            if definition.frames.is_empty() {
+                return Err(AssetError::format(
+                    ASSET,
+                    format!("animation of tile {} has no frames", key),
+                ));
+            }
+
+            let durations = match definition.durations {
+                Some(durations) if durations.len() != definition.frames.len() => {
+                    return Err(AssetError::format(
+                        ASSET,
+                        format!(
+                            "animation of tile {} has {} frames but {} durations",
+                            key,
+                            definition.frames.len(),
+                            durations.len()
+                        ),
+                    ))
+                }
+                Some(durations) => durations,
+                None => vec![definition.duration; definition.frames.len()],
+            };
+
+            if durations.iter().any(|&duration| duration <= 0.0) {
+                return Err(AssetError::format(
+                    ASSET,
+                    format!("animation of tile {} has a duration of zero or less", key),
+                ));
+            }
+
+            let animation = TileAnimation {
+                frames: definition
+                    .frames
+                    .iter()
+                    .map(|id| parse_id(id))
+                    .collect::<Result<Vec<u32>, AssetError>>()?,
+                durations,
+                mode: definition.mode,
+            };
+
+            tileset.animations.insert(parse_id(&key)?, animation);
+        }
+
+        Ok(tileset)
+    }
+    pub fn get(&self, id: u32) -> TileProperties {
+        self.tiles.get(&id).copied().unwrap_or(self.default)
+    }
+    pub fn set(&mut self, id: u32, properties: TileProperties) {
+        self.tiles.insert(id, properties);
+    }
+    pub fn set_default(&mut self, properties: TileProperties) {
+        self.default = properties;
+    }
+    // Terrain a tile is part of, tiles of no terrain are never autotiled
+    pub fn terrain(&self, id: u32) -> Option<&Terrain> {
+        self.terrains.iter().find(|t| t.contains(id))
+    }
+}
+
+fn parse_id(id: &str) -> Result<u32, AssetError> {
+    u32::from_str_radix(id, 16)
+        .map_err(|_| AssetError::format(ASSET, format!("tile id {} is not hexadecimal", id)))
+}
+
+#[derive(Deserialize)]
+struct Definition {
+    #[serde(default)]
+    default: TileProperties,
+    #[serde(default)]
+    tiles: BTreeMap<String, TileProperties>,
+    #[serde(default)]
+    terrains: Vec<TerrainDefinition>,
+    #[serde(default)]
+    animations: BTreeMap<String, AnimationDefinition>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TerrainDefinition {
+    name: String,
+    #[serde(default = "four")]
+    mask: u8,
+    tiles: BTreeMap<String, String>,
+    default: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AnimationDefinition {
+    frames: Vec<String>,
+    #[serde(default = "quarter")]
+    duration: f32,
+    durations: Option<Vec<f32>>,
+    #[serde(default)]
+    mode: Mode,
+}
+
+fn four() -> u8 {
+    4
+}
+
+fn quarter() -> f32 {
+    0.25
+}
@@ -0,0 +1,446 @@
+use std::f32::consts::PI;
+
+use super::color::Color;
+use super::point::Point;
+use super::rectangle::Rectangle;
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum Easing {
+    #[default]
+    Linear,
+    QuadIn,
+    QuadOut,
+    QuadInOut,
+    CubicIn,
+    CubicOut,
+    CubicInOut,
+    QuartIn,
+    QuartOut,
+    QuartInOut,
+    QuintIn,
+    QuintOut,
+    QuintInOut,
+    SineIn,
+    SineOut,
+    SineInOut,
+    ExpoIn,
+    ExpoOut,
+    ExpoInOut,
+    CircIn,
+    CircOut,
+    CircInOut,
+    BackIn,
+    BackOut,
+    BackInOut,
+    ElasticIn,
+    ElasticOut,
+    ElasticInOut,
+    BounceIn,
+    BounceOut,
+    BounceInOut,
+}
+
+impl Easing {
+    // Map linear progress in 0..1 to eased progress, back and elastic overshoot the range
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+
+        let c1 = 1.70158;
+        let c2 = c1 * 1.525;
+        let c3 = c1 + 1.0;
+        let c4 = (2.0 * PI) / 3.0;
+        let c5 = (2.0 * PI) / 4.5;
+
+        let in_out = |t: f32, f: fn(f32) -> f32| {
+            if t < 0.5 {
+                f(t * 2.0) / 2.0
+            } else {
+                1.0 - f((1.0 - t) * 2.0) / 2.0
+            }
+        };
+
+        match self {
+            Easing::Linear => t,
+            Easing::QuadIn => t.powi(2),
+            Easing::QuadOut => 1.0 - (1.0 - t).powi(2),
+            Easing::QuadInOut => in_out(t, |t| t.powi(2)),
+            Easing::CubicIn => t.powi(3),
+            Easing::CubicOut => 1.0 - (1.0 - t).powi(3),
+            Easing::CubicInOut => in_out(t, |t| t.powi(3)),
+            Easing::QuartIn => t.powi(4),
+            Easing::QuartOut => 1.0 - (1.0 - t).powi(4),
+            Easing::QuartInOut => in_out(t, |t| t.powi(4)),
+            Easing::QuintIn => t.powi(5),
+            Easing::QuintOut => 1.0 - (1.0 - t).powi(5),
+            Easing::QuintInOut => in_out(t, |t| t.powi(5)),
+            Easing::SineIn => 1.0 - (t * PI / 2.0).cos(),
+            Easing::SineOut => (t * PI / 2.0).sin(),
+            Easing::SineInOut => -((PI * t).cos() - 1.0) / 2.0,
+            Easing::ExpoIn => expo(t),
+            Easing::ExpoOut => 1.0 - expo(1.0 - t),
+            Easing::ExpoInOut => in_out(t, expo),
+            Easing::CircIn => circ(t),
+            Easing::CircOut => 1.0 - circ(1.0 - t),
+            Easing::CircInOut => in_out(t, circ),
+            Easing::BackIn => c3 * t.powi(3) - c1 * t.powi(2),
+            Easing::BackOut => 1.0 + c3 * (t - 1.0).powi(3) + c1 * (t - 1.0).powi(2),
+            Easing::BackInOut => {
+                if t < 0.5 {
+                    ((2.0 * t).powi(2) * ((c2 + 1.0) * 2.0 * t - c2)) / 2.0
+                } else {
+                    ((2.0 * t - 2.0).powi(2) * ((c2 + 1.0) * (t * 2.0 - 2.0) + c2) + 2.0) / 2.0
+                }
+            }
+            Easing::ElasticIn => {
+                if t == 0.0 || t == 1.0 {
+                    t
+                } else {
+                    -(2.0f32.powf(10.0 * t - 10.0)) * ((t * 10.0 - 10.75) * c4).sin()
+                }
+            }
+            Easing::ElasticOut => {
+                if t == 0.0 || t == 1.0 {
+                    t
+                } else {
+                    2.0f32.powf(-10.0 * t) * ((t * 10.0 - 0.75) * c4).sin() + 1.0
+                }
+            }
+            Easing::ElasticInOut => {
+                if t == 0.0 || t == 1.0 {
+                    t
+                } else if t < 0.5 {
+                    -(2.0f32.powf(20.0 * t - 10.0) * ((20.0 * t - 11.125) * c5).sin()) / 2.0
+                } else {
+                    (2.0f32.powf(-20.0 * t + 10.0) * ((20.0 * t - 11.125) * c5).sin()) / 2.0 + 1.0
+                }
+            }
+            Easing::BounceIn => 1.0 - bounce(1.0 - t),
+            Easing::BounceOut => bounce(t),
+            Easing::BounceInOut => in_out(t, |t| 1.0 - bounce(1.0 - t)),
+        }
+    }
+}
+
+fn expo(t: f32) -> f32 {
+    if t == 0.0 {
+        0.0
+    } else {
+        2.0f32.powf(10.0 * t - 10.0)
+    }
+}
+
+fn circ(t: f32) -> f32 {
+    1.0 - (1.0 - t.powi(2)).max(0.0).sqrt()
+}
+
+fn bounce(t: f32) -> f32 {
+    let n1 = 7.5625;
+    let d1 = 2.75;
+
+    if t < 1.0 / d1 {
+        n1 * t * t
+    } else if t < 2.0 / d1 {
+        let t = t - 1.5 / d1;
+        n1 * t * t + 0.75
+    } else if t < 2.5 / d1 {
+        let t = t - 2.25 / d1;
+        n1 * t * t + 0.9375
+    } else {
+        let t = t - 2.625 / d1;
+        n1 * t * t + 0.984375
+    }
+}
+
+// Values that can be interpolated, t may leave 0..1 for overshooting easings
+pub trait Tweenable: Copy {
+    fn lerp(&self, to: &Self, t: f32) -> Self;
+}
+
+impl Tweenable for f32 {
+    fn lerp(&self, to: &Self, t: f32) -> Self {
+        self + (to - self) * t
+    }
+}
+
+impl Tweenable for Point {
+    fn lerp(&self, to: &Self, t: f32) -> Self {
+        Point::new(self.x.lerp(&to.x, t), self.y.lerp(&to.y, t))
+    }
+}
+
+impl Tweenable for Rectangle {
+    fn lerp(&self, to: &Self, t: f32) -> Self {
+        Rectangle::new(
+            self.x.lerp(&to.x, t),
+            self.y.lerp(&to.y, t),
+            self.width.lerp(&to.width, t).max(0.0),
+            self.height.lerp(&to.height, t).max(0.0),
+        )
+    }
+}
+
+impl Tweenable for Color {
+    fn lerp(&self, to: &Self, t: f32) -> Self {
+        let channel =
+            |a: u8, b: u8| (a as f32).lerp(&(b as f32), t).round().clamp(0.0, 255.0) as u8;
+
+        Color::new(
+            channel(self.red, to.red),
+            channel(self.green, to.green),
+            channel(self.blue, to.blue),
+            channel(self.alpha, to.alpha),
+        )
+    }
+}
+
+pub type Complete<T> = fn(&Tween<T>);
+
+#[derive(Debug, Clone, Default)]
+pub struct Tween<T> {
+    from: T,
+    to: T,
+    duration: f32,
+    delay: f32,
+    easing: Easing,
+    repeat: Option<u32>,
+    yoyo: bool,
+    elapsed: f32,
+    waited: f32,
+    passes: u32,
+    forward: bool,
+    active: bool,
+    finished: bool,
+    on_complete: Option<Complete<T>>,
+}
+
+impl<T: Tweenable> Tween<T> {
+    pub fn new(from: T, to: T, duration: f32, easing: Easing) -> Self {
+        Self {
+            from,
+            to,
+            duration: duration.max(0.0),
+            delay: 0.0,
+            easing,
+            repeat: Some(0),
+            yoyo: false,
+            elapsed: 0.0,
+            waited: 0.0,
+            passes: 0,
+            forward: true,
+            active: true,
+            finished: false,
+            on_complete: None,
+        }
+    }
+    // Tween that already rests at its value
+    pub fn still(value: T) -> Self {
+        let mut tween = Self::new(value, value, 0.0, Easing::Linear);
+        tween.finished = true;
+        tween
+    }
+    pub fn delay(mut self, delay: f32) -> Self {
+        self.delay = delay.max(0.0);
+        self
+    }
+    // Additional passes after the first one
+    pub fn repeat(mut self, count: u32) -> Self {
+        self.repeat = Some(count);
+        self
+    }
+    pub fn forever(mut self) -> Self {
+        self.repeat = None;
+        self
+    }
+    // Alternate direction on every repeat
+    pub fn yoyo(mut self) -> Self {
+        self.yoyo = true;
+        self
+    }
+    pub fn on_complete(mut self, callback: Complete<T>) -> Self {
+        self.on_complete = Some(callback);
+        self
+    }
+    pub fn from(&self) -> T {
+        self.from
+    }
+    pub fn to(&self) -> T {
+        self.to
+    }
+    pub fn duration(&self) -> f32 {
+        self.duration
+    }
+    pub fn passes(&self) -> u32 {
+        self.passes
+    }
+    pub fn finished(&self) -> bool {
+        self.finished
+    }
+    pub fn start(&mut self) {
+        self.active = true;
+    }
+    pub fn stop(&mut self) {
+        self.active = false;
+    }
+    pub fn restart(&mut self) {
+        self.elapsed = 0.0;
+        self.waited = 0.0;
+        self.passes = 0;
+        self.forward = true;
+        self.active = true;
+        self.finished = false;
+    }
+    // Eased progress of the current pass
+    pub fn progress(&self) -> f32 {
+        let t = if self.duration > 0.0 {
+            self.elapsed / self.duration
+        } else {
+            1.0
+        };
+        let t = if self.forward { t } else { 1.0 - t };
+
+        self.easing.apply(t)
+    }
+    pub fn value(&self) -> T {
+        self.from.lerp(&self.to, self.progress())
+    }
+    // Advance by game time and return the time left over once finished
+    pub fn update(&mut self, delta: f32) -> f32 {
+        if self.finished || !self.active {
+            return 0.0;
+        }
+
+        let mut delta = delta;
+
+        if self.waited < self.delay {
+            let wait = delta.min(self.delay - self.waited);
+            self.waited += wait;
+            delta -= wait;
+        }
+
+        self.elapsed += delta;
+
+        while self.elapsed >= self.duration {
+            let exhausted = self.repeat.is_some_and(|repeat| self.passes >= repeat);
+
+            if exhausted || self.duration <= 0.0 {
+                let leftover = self.elapsed - self.duration;
+                self.elapsed = self.duration;
+                self.finished = self.repeat.is_some();
+
+                if self.finished {
+                    if let Some(callback) = self.on_complete {
+                        callback(self);
+                    }
+                    return leftover;
+                }
+                return 0.0;
+            }
+
+            self.elapsed -= self.duration;
+            self.passes += 1;
+
+            if self.yoyo {
+                self.forward = !self.forward;
+            }
+        }
+
+        0.0
+    }
+}
+
+// Tweens played back to back, optionally repeated as a whole
+#[derive(Debug, Clone, Default)]
+pub struct Sequence<T> {
+    steps: Vec<Tween<T>>,
+    index: usize,
+    repeat: Option<u32>,
+    passes: u32,
+    finished: bool,
+    on_complete: Option<fn(&Sequence<T>)>,
+}
+
+impl<T: Tweenable> Sequence<T> {
+    pub fn new(steps: Vec<Tween<T>>) -> Self {
+        if steps.is_empty() {
+            panic!("Sequence should contain at least one tween");
+        }
+
+        Self {
+            steps,
+            index: 0,
+            repeat: Some(0),
+            passes: 0,
+            finished: false,
+            on_complete: None,
+        }
+    }
+    pub fn then(mut self, step: Tween<T>) -> Self {
+        self.steps.push(step);
+        self
+    }
+    pub fn repeat(mut self, count: u32) -> Self {
+        self.repeat = Some(count);
+        self
+    }
+    pub fn forever(mut self) -> Self {
+        self.repeat = None;
+        self
+    }
+    pub fn on_complete(mut self, callback: fn(&Sequence<T>)) -> Self {
+        self.on_complete = Some(callback);
+        self
+    }
+    pub fn index(&self) -> usize {
+        self.index
+    }
+    pub fn finished(&self) -> bool {
+        self.finished
+    }
+    pub fn restart(&mut self) {
+        self.index = 0;
+        self.passes = 0;
+        self.finished = false;
+        for step in self.steps.iter_mut() {
+            step.restart();
+        }
+    }
+    pub fn value(&self) -> T {
+        self.steps[self.index].value()
+    }
+    pub fn update(&mut self, delta: f32) {
+        let mut delta = delta;
+        // Bound the work per update so zero length steps repeated forever cannot spin
+        let mut budget = self.steps.len() * 2 + 1;
+
+        while !self.finished && budget > 0 {
+            budget -= 1;
+
+            let leftover = self.steps[self.index].update(delta);
+
+            if !self.steps[self.index].finished() {
+                return;
+            }
+
+            delta = leftover;
+
+            if self.index + 1 < self.steps.len() {
+                self.index += 1;
+                continue;
+            }
+
+            if self.repeat.is_some_and(|repeat| self.passes >= repeat) {
+                self.finished = true;
+                if let Some(callback) = self.on_complete {
+                    callback(self);
+                }
+                return;
+            }
+
+            self.passes += 1;
+            self.index = 0;
+            for step in self.steps.iter_mut() {
+                step.restart();
+            }
+        }
+    }
+}
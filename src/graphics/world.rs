@@ -0,0 +1,179 @@
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+
+use super::asset::{AssetError, Context};
+use super::point::Point;
+use super::rectangle::Rectangle;
+
+const ASSET: &str = "Level registry";
+
+// Level and entry point a game starts at or an exit leads to
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Target {
+    pub level: String,
+    pub entry: String,
+}
+
+// Area of a level that loads another one, doors are only taken when pressing up
+#[derive(Debug, Clone, Default)]
+pub struct Exit {
+    pub area: Rectangle,
+    pub target: Target,
+    pub door: bool,
+}
+
+impl Exit {
+    // Exit area scaled to level units
+    pub fn bounds(&self, tile_width: f32, tile_height: f32) -> Rectangle {
+        Rectangle::new(
+            self.area.x * tile_width,
+            self.area.y * tile_height,
+            self.area.width * tile_width,
+            self.area.height * tile_height,
+        )
+    }
+}
+
+// Registry entry of a level, positions and areas are in tiles
+#[derive(Debug, Clone, Default)]
+pub struct WorldLevel {
+    pub name: String,
+    pub map: String,
+    pub size: Option<(u32, u32)>,
+    pub entries: BTreeMap<String, Point>,
+    pub exits: Vec<Exit>,
+}
+
+// Levels by name with the level and entry point a game starts at
+#[derive(Debug, Clone, Default)]
+pub struct World {
+    pub start: Target,
+    pub levels: BTreeMap<String, WorldLevel>,
+}
+
+impl World {
+    pub fn from_uri(uri: &str) -> Result<Self, AssetError> {
+        Self::from_json(&std::fs::read_to_string(uri).context(ASSET)?)
+    }
+    // The start and every exit have to lead to a registered level, entries may also be
+    // spawn entities of its map so they are checked once the level is entered
+    pub fn from_json(json: &str) -> Result<Self, AssetError> {
+        let data: Definition = serde_json::from_str(json).context(ASSET)?;
+
+        let mut world = Self {
+            start: data.start,
+            levels: BTreeMap::new(),
+        };
+
+        for (name, definition) in data.levels.into_iter() {
+            let size = match (definition.columns, definition.rows) {
+                (Some(columns), Some(rows)) => Some((columns, rows)),
+                (None, None) => None,
+                _ => {
+                    return Err(AssetError::format(
+                        ASSET,
+                        format!("level {} should set both columns and rows", name),
+                    ))
+                }
+            };
+
+            let entries = definition
+                .entries
+                .into_iter()
+                .map(|(entry, [x, y])| (entry, Point::new(x, y)))
+                .collect();
+
+            let exits = definition
+                .exits
+                .into_iter()
+                .map(|exit| Exit {
+                    area: Rectangle::new(exit.area[0], exit.area[1], exit.area[2], exit.area[3]),
+                    target: Target {
+                        level: exit.level,
+                        entry: exit.entry,
+                    },
+                    door: exit.door,
+                })
+                .collect();
+
+            let level = WorldLevel {
+                name: name.clone(),
+                map: definition.map,
+                size,
+                entries,
+                exits,
+            };
+
+            world.levels.insert(name, level);
+        }
+
+        world.check(&world.start)?;
+
+        for level in world.levels.values() {
+            for exit in level.exits.iter() {
+                world.check(&exit.target)?;
+            }
+        }
+
+        Ok(world)
+    }
+    pub fn level(&self, name: &str) -> Option<&WorldLevel> {
+        self.levels.get(name)
+    }
+    // Entry point of a target in tiles
+    pub fn entry(&self, target: &Target) -> Option<Point> {
+        self.level(&target.level)?
+            .entries
+            .get(&target.entry)
+            .copied()
+    }
+    // The start and registry exits leading into a level
+    pub fn targets(&self, level: &str) -> Vec<&Target> {
+        let exits = self.levels.values().flat_map(|l| l.exits.iter());
+
+        std::iter::once(&self.start)
+            .chain(exits.map(|exit| &exit.target))
+            .filter(|target| target.level == level)
+            .collect()
+    }
+    fn check(&self, target: &Target) -> Result<(), AssetError> {
+        match self.level(&target.level) {
+            Some(_) => Ok(()),
+            None => Err(AssetError::format(
+                ASSET,
+                format!("level {} is not registered", target.level),
+            )),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Definition {
+    start: Target,
+    levels: BTreeMap<String, LevelDefinition>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LevelDefinition {
+    map: String,
+    columns: Option<u32>,
+    rows: Option<u32>,
+    #[serde(default)]
+    entries: BTreeMap<String, [f32; 2]>,
+    #[serde(default)]
+    exits: Vec<ExitDefinition>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ExitDefinition {
+    area: [f32; 4],
+    level: String,
+    entry: String,
+    #[serde(default)]
+    door: bool,
+}
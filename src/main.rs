@@ -4,6 +4,40 @@ use platform_prototype::game::data::{FILTER, HEIGHT, RESIZABLE, SCALE, TITLE, WI
 use platform_prototype::game::state::State;
 
 fn main() {
-    let state = State::build();
+    let arguments = std::env::args().collect::<Vec<String>>();
+
+    let map = arguments
+        .iter()
+        .position(|argument| argument == "--map")
+        .and_then(|index| arguments.get(index + 1));
+
+    let export = arguments
+        .iter()
+        .position(|argument| argument == "--export")
+        .and_then(|index| arguments.get(index + 1));
+
+    let state = match map {
+        Some(uri) => State::from_tiled(uri).unwrap_or_else(|error| {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }),
+        None => State::build(),
+    };
+
+    // Write the level in the format of the file extension instead of playing it
+    if let Some(uri) = export {
+        if let Err(error) = state.env.level().save(uri) {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    #[cfg(unix)]
+    if arguments.iter().any(|argument| argument == "--terminal") {
+        platform_prototype::display::run_terminal(WIDTH, HEIGHT, TITLE.into(), state);
+        return;
+    }
+
     run(WIDTH, HEIGHT, SCALE, RESIZABLE, FILTER, TITLE.into(), state);
 }
@@ -0,0 +1,66 @@
+use platform_prototype::game::data::{ENV_HEIGHT, ENV_WIDTH, TILE_HEIGHT, TILE_WIDTH};
+use platform_prototype::game::data::{FRICTION, GRAVITY, RESISTANCE};
+use platform_prototype::game::state::{Environment, State};
+use platform_prototype::graphics::level::Level;
+
+// Shipped maps read back into the same text, layers and entities included, and export
+fn round_trip(uri: &str, columns: u32, rows: u32) {
+    let map = std::fs::read_to_string(uri).expect("Map file should be readable");
+    let environment = Environment::from_map(&map, columns, rows).expect("Map should be valid");
+
+    assert_eq!(environment.map().expect("Level should serialize"), map);
+
+    let level = environment.level();
+    assert!(level.to_json().is_ok() && level.to_ron().is_ok());
+    check_metadata(&level);
+}
+
+// Exported physics are the configured values, not the per step factors of a running game
+fn check_metadata(level: &Level) {
+    let value = |name: &str| level.metadata.get(name).and_then(|p| p.as_f32());
+
+    assert_eq!(value("gravity"), Some(GRAVITY));
+    assert_eq!(value("friction"), Some(FRICTION));
+    assert_eq!(value("resistance"), Some(RESISTANCE));
+}
+
+#[test]
+fn level_map_round_trip() {
+    round_trip(
+        "assets/level.map",
+        ENV_WIDTH / TILE_WIDTH,
+        ENV_HEIGHT / TILE_HEIGHT,
+    );
+}
+
+#[test]
+fn cave_map_round_trip() {
+    round_trip("assets/cave.map", 36, 18);
+}
+
+// CRLF line endings, a trailing newline and unpadded rows read back in the written form
+#[test]
+fn map_normalization() {
+    let map = std::fs::read_to_string("assets/cave.map").expect("Map file should be readable");
+
+    let unpadded = map.split('\n').map(|l| l.trim_end()).collect::<Vec<&str>>();
+    let variants = [
+        map.replace('\n', "\r\n"),
+        format!("{}\n", map),
+        format!("{}\r\n", map.replace('\n', "\r\n")),
+        unpadded.join("\n"),
+    ];
+
+    for variant in variants.iter() {
+        let environment = Environment::from_map(variant, 36, 18).expect("Map should be valid");
+        assert_eq!(environment.map().expect("Level should serialize"), map);
+    }
+}
+
+#[test]
+fn started_level_export() {
+    check_metadata(&State::build().env.level());
+
+    let state = State::from_tiled("assets/level.tmx").expect("Tiled map should be valid");
+    check_metadata(&state.env.level());
+}